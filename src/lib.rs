@@ -17,6 +17,13 @@
 //! used with other input texts by creating an instance of
 //! [`MarkovChain`] and calling its [`learn`] method.
 //!
+//! The functions which take a random number generator need the
+//! `rand` feature, which is enabled by default. Disable it to drop
+//! the dependency on the `rand` crate, e.g., for small WebAssembly
+//! builds. The [`lipsum`] function is still available: it then
+//! selects words with a small built-in xorshift generator, so the
+//! text differs from the default build once it becomes random.
+//!
 //! [wiki]: https://en.wikipedia.org/wiki/Lorem_ipsum
 //! [`lipsum`]: fn.lipsum.html
 //! [`MarkovChain`]: struct.MarkovChain.html
@@ -27,10 +34,25 @@
 #![forbid(unsafe_code)]
 #![deny(missing_docs)]
 
+#[cfg(feature = "rand")]
 use rand::seq::SliceRandom;
+#[cfg(feature = "rand")]
 use rand::{Rng, SeedableRng};
+#[cfg(feature = "rand")]
 use rand_chacha::ChaCha20Rng;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+#[cfg(feature = "rand")]
+use std::io::Write;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+#[cfg(not(feature = "rand"))]
+use xorshift::{Rng, SliceRandom, XorShift};
 
 /// A bigram is simply two consecutive words.
 pub type Bigram<'a> = (&'a str, &'a str);
@@ -43,12 +65,30 @@ pub type Bigram<'a> = (&'a str, &'a str);
 /// looks somewhat plausible. The implementation is based on
 /// [Generating arbitrary text with Markov chains in Rust][blog post].
 ///
+/// Two chains are equal if they have learned the same transitions
+/// the same number of times and the same sentence starts with
+/// [`learn_sentence`], regardless of the order in which the text was
+/// learned. The `Hash` implementation and [`fingerprint`] are
+/// consistent with this. The starts of texts learned with other
+/// methods and the word counts used for estimates are ignored.
+///
+/// [`learn_sentence`]: struct.MarkovChain.html#method.learn_sentence
+/// [`fingerprint`]: struct.MarkovChain.html#method.fingerprint
+///
 /// [Markov chain]: https://en.wikipedia.org/wiki/Markov_chain
 /// [blog post]: https://blakewilliams.me/posts/generating-arbitrary-text-with-markov-chains-in-rust
 #[derive(Debug, Clone, Default)]
 pub struct MarkovChain<'a> {
     map: HashMap<Bigram<'a>, Vec<&'a str>>,
     keys: Vec<Bigram<'a>>,
+    /// First bigram of each sentence learned with `learn_sentence`.
+    starts: Vec<Bigram<'a>>,
+    /// First bigram of each text learned.
+    text_starts: Vec<Bigram<'a>>,
+    /// Total number of words learned.
+    word_count: usize,
+    /// Total number of words ending a sentence learned.
+    sentence_count: usize,
 }
 
 impl<'a> MarkovChain<'a> {
@@ -80,27 +120,453 @@ impl<'a> MarkovChain<'a> {
     /// Add new text to the Markov chain. This can be called several
     /// times to build up the chain.
     ///
+    /// Returns the number of new states, i.e., bigrams which were not
+    /// already in the chain. This is zero when the text adds no new
+    /// states, e.g., because it has been learned before.
+    ///
+    /// The states are kept sorted as they are learned, so the chain
+    /// is ready for generating text after each call. There is no
+    /// separate step needed after learning a batch of texts.
+    ///
     /// # Examples
     ///
     /// ```
     /// use lipsum::MarkovChain;
     ///
     /// let mut chain = MarkovChain::new();
-    /// chain.learn("red green blue");
+    /// assert_eq!(chain.learn("red green blue"), 1);
     /// assert_eq!(chain.words(("red", "green")), Some(&vec!["blue"]));
     ///
-    /// chain.learn("red green yellow");
+    /// assert_eq!(chain.learn("red green yellow"), 0);
     /// assert_eq!(chain.words(("red", "green")), Some(&vec!["blue", "yellow"]));
     /// ```
-    pub fn learn(&mut self, sentence: &'a str) {
+    pub fn learn(&mut self, sentence: &'a str) -> usize {
         let words = sentence.split_whitespace().collect::<Vec<&str>>();
-        for window in words.windows(3) {
+        self.learn_words(&words)
+    }
+
+    /// Add text with several paragraphs to the Markov chain.
+    ///
+    /// Paragraphs are separated by blank lines, i.e., lines with
+    /// nothing but whitespace. The text is learned like with
+    /// [`learn`], but the paragraph boundaries are recorded as
+    /// transitions too. Use [`Options::paragraph_breaks`] to generate
+    /// text with paragraphs at the learned boundaries.
+    ///
+    /// Returns the number of new states.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn_paragraphs("One two three.\n\nFour five six.\n\nOne two three.");
+    /// let options = Options::new().paragraph_breaks(true);
+    /// let rng = ChaCha20Rng::seed_from_u64(3);
+    /// assert_eq!(
+    ///     chain.generate_with_options(rng, 6, &options),
+    ///     "Four five six.\n\nOne two three."
+    /// );
+    /// ```
+    ///
+    /// [`learn`]: struct.MarkovChain.html#method.learn
+    /// [`Options::paragraph_breaks`]: struct.Options.html#structfield.paragraph_breaks
+    pub fn learn_paragraphs(&mut self, text: &'a str) -> usize {
+        let mut tokens = Vec::new();
+        for line in text.lines() {
+            let mut words = line.split_whitespace().peekable();
+            if words.peek().is_none() {
+                if !tokens.is_empty() && tokens.last() != Some(&PARAGRAPH_BREAK) {
+                    tokens.push(PARAGRAPH_BREAK);
+                }
+                continue;
+            }
+            tokens.extend(words);
+        }
+        if tokens.last() == Some(&PARAGRAPH_BREAK) {
+            tokens.pop();
+        }
+        self.learn_words(&tokens)
+    }
+
+    /// Add several pieces of text to the Markov chain in a random
+    /// order.
+    ///
+    /// The `sentences` are shuffled using `rng` and then learned one
+    /// by one with [`learn`]. Returns the total number of new states.
+    ///
+    /// The order of learning does not change which transitions are in
+    /// the chain, nor how often they occur, and the states are kept
+    /// sorted. However, the order of the successors of each bigram
+    /// follows the learning order. Text generated with a fixed seed
+    /// can therefore differ depending on the order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// chain.learn_shuffled(&["a b c", "a b d", "a b e"], rng);
+    ///
+    /// let mut successors = chain.words(("a", "b")).unwrap().clone();
+    /// successors.sort();
+    /// assert_eq!(successors, vec!["c", "d", "e"]);
+    /// ```
+    ///
+    /// [`learn`]: struct.MarkovChain.html#method.learn
+    #[cfg(feature = "rand")]
+    pub fn learn_shuffled<R: Rng>(&mut self, sentences: &[&'a str], mut rng: R) -> usize {
+        let mut sentences = sentences.to_vec();
+        sentences.shuffle(&mut rng);
+        sentences
+            .into_iter()
+            .map(|sentence| self.learn(sentence))
+            .sum()
+    }
+
+    /// Add a single sentence to the Markov chain.
+    ///
+    /// This works like [`learn`], but also records where the sentence
+    /// starts and ends. The end is recorded as a transition to an
+    /// empty string, which is never generated. When the generated
+    /// text reaches the end of a sentence, it restarts at the start
+    /// of a random sentence learned with this method. Random text
+    /// generated by, e.g., [`iter_with_rng`] also starts at the start
+    /// of such a sentence.
+    ///
+    /// Sentences with less than two words are counted, but add no
+    /// states to the chain.
+    ///
+    /// Returns the number of new states.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn_sentence("The quick brown fox jumps.");
+    /// chain.learn_sentence("The lazy dog sleeps.");
+    ///
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// let text = chain.generate_with_rng(rng, 10);
+    /// assert!(text.starts_with("The "));
+    /// ```
+    ///
+    /// [`learn`]: struct.MarkovChain.html#method.learn
+    /// [`iter_with_rng`]: struct.MarkovChain.html#method.iter_with_rng
+    pub fn learn_sentence(&mut self, sentence: &'a str) -> usize {
+        let mut words = sentence.split_whitespace().collect::<Vec<&str>>();
+        self.word_count += words.len();
+        self.sentence_count += 1;
+        if let [a, b, ..] = words[..] {
+            self.starts.push((a, b));
+        }
+        words.push(SENTENCE_END);
+        self.learn_tokens(&words)
+    }
+
+    /// Add new text to the Markov chain after normalizing it.
+    ///
+    /// This works like [`learn`], but is more forgiving with messy
+    /// text, such as text scraped from web pages. In addition to
+    /// whitespace, words are separated by:
+    ///
+    /// * control characters, such as `'\0'` or `'\u{7f}'`,
+    /// * zero-width spaces and joiners (`'\u{200b}'` to `'\u{200d}'`),
+    /// * byte order marks (`'\u{feff}'`).
+    ///
+    /// These characters are thus never part of the learned words.
+    /// Like with [`learn`], any amount of whitespace between words is
+    /// treated as a single space.
+    ///
+    /// Returns the number of new states, just like [`learn`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn_clean("\u{feff}red\u{200b}green\x07 \t blue\r\n");
+    /// assert_eq!(chain.words(("red", "green")), Some(&vec!["blue"]));
+    /// ```
+    ///
+    /// [`learn`]: struct.MarkovChain.html#method.learn
+    pub fn learn_clean(&mut self, text: &'a str) -> usize {
+        let is_separator = |c: char| {
+            c.is_whitespace() || c.is_control() || matches!(c, '\u{200b}'..='\u{200d}' | '\u{feff}')
+        };
+        let words = text
+            .split(is_separator)
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<&str>>();
+        self.learn_words(&words)
+    }
+
+    /// Remove everything learned by the Markov chain.
+    ///
+    /// The chain is left empty like a new chain, but the allocated
+    /// memory is kept for learning new text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red green blue");
+    /// chain.clear();
+    /// assert!(chain.is_empty());
+    /// ```
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.keys.clear();
+        self.starts.clear();
+        self.text_starts.clear();
+        self.word_count = 0;
+        self.sentence_count = 0;
+    }
+
+    /// Replace the contents of the Markov chain with `text`.
+    ///
+    /// This is the same as calling [`clear`] followed by [`learn`],
+    /// which reuses the allocated memory. Use this when reloading a
+    /// changed text. Returns the number of states, like [`learn`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red green blue");
+    /// assert_eq!(chain.relearn("red green yellow"), 1);
+    /// assert_eq!(chain.words(("red", "green")), Some(&vec!["yellow"]));
+    /// ```
+    ///
+    /// [`clear`]: struct.MarkovChain.html#method.clear
+    /// [`learn`]: struct.MarkovChain.html#method.learn
+    pub fn relearn(&mut self, text: &'a str) -> usize {
+        self.clear();
+        self.learn(text)
+    }
+
+    /// Add several texts to the Markov chain.
+    ///
+    /// This is equivalent to calling [`learn`] on each text, but the
+    /// states are only sorted once at the end. This makes it faster
+    /// to learn many small texts.
+    ///
+    /// Returns the total number of new states.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// assert_eq!(chain.learn_all(&["red green blue", "red green yellow"]), 1);
+    /// assert_eq!(chain.words(("red", "green")), Some(&vec!["blue", "yellow"]));
+    /// ```
+    ///
+    /// [`learn`]: struct.MarkovChain.html#method.learn
+    pub fn learn_all(&mut self, texts: &[&'a str]) -> usize {
+        let mut new_states = 0;
+        for text in texts {
+            let words = text.split_whitespace().collect::<Vec<&str>>();
+            new_states += self.add_words(&words);
+        }
+        if new_states > 0 {
+            self.keys.sort_unstable();
+        }
+        new_states
+    }
+
+    /// Add a sequence of words to the Markov chain. Returns the number
+    /// of new states.
+    fn learn_words(&mut self, words: &[&'a str]) -> usize {
+        let new_states = self.add_words(words);
+        if new_states > 0 {
+            self.keys.sort_unstable();
+        }
+        new_states
+    }
+
+    /// Add a sequence of words like `learn_words`, but without sorting
+    /// the keys.
+    fn add_words(&mut self, words: &[&'a str]) -> usize {
+        if let [a, b, ..] = *words {
+            self.text_starts.push((a, b));
+        }
+        self.word_count += words.iter().filter(|&&w| w != PARAGRAPH_BREAK).count();
+        self.sentence_count += words.iter().filter(|w| w.ends_with(TERMINATORS)).count();
+        self.add_tokens(words)
+    }
+
+    /// Add new text to the Markov chain, one character at a time.
+    ///
+    /// This makes a character-level Markov chain: each character
+    /// (including whitespace) becomes a token and the bigrams are
+    /// pairs of consecutive characters. Use [`generate_chars`] to
+    /// generate pseudo-words from such a chain. Mixing character-level
+    /// and word-level learning in the same chain is possible, but is
+    /// unlikely to give useful results.
+    ///
+    /// Returns the number of new states, just like [`learn`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn_char_level("abcabd");
+    /// assert_eq!(chain.words(("a", "b")), Some(&vec!["c", "d"]));
+    /// ```
+    ///
+    /// [`learn`]: struct.MarkovChain.html#method.learn
+    /// [`generate_chars`]: struct.MarkovChain.html#method.generate_chars
+    pub fn learn_char_level(&mut self, text: &'a str) -> usize {
+        let chars = text
+            .char_indices()
+            .map(|(idx, c)| &text[idx..idx + c.len_utf8()])
+            .collect::<Vec<&str>>();
+        self.learn_tokens(&chars)
+    }
+
+    /// Add transitions between consecutive tokens to the Markov chain.
+    /// Returns the number of new states.
+    fn learn_tokens(&mut self, tokens: &[&'a str]) -> usize {
+        let new_states = self.add_tokens(tokens);
+        if new_states > 0 {
+            // Keep the keys sorted.
+            self.keys.sort_unstable();
+        }
+        new_states
+    }
+
+    /// Add transitions like `learn_tokens`, but without sorting the
+    /// keys.
+    fn add_tokens(&mut self, tokens: &[&'a str]) -> usize {
+        let mut new_states = 0;
+        for window in tokens.windows(3) {
             let (a, b, c) = (window[0], window[1], window[2]);
-            self.map.entry((a, b)).or_default().push(c);
+            match self.map.entry((a, b)) {
+                Entry::Occupied(mut entry) => entry.get_mut().push(c),
+                Entry::Vacant(entry) => {
+                    entry.insert(vec![c]);
+                    self.keys.push((a, b));
+                    new_states += 1;
+                }
+            }
+        }
+        new_states
+    }
+
+    /// Retain only the transitions specified by the predicate.
+    ///
+    /// The predicate is called with each bigram and each of its
+    /// successors. Successors for which `f` returns `false` are
+    /// removed. Bigrams left without successors are removed from the
+    /// chain entirely. This can be used to post-process a learned
+    /// chain, e.g., to remove words matching a blocklist.
+    ///
+    /// Removing transitions can create new dead ends in the chain.
+    /// Like when the last bigram of the learned text is reached, the
+    /// generated text will then continue from a random point.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("one two three one two four");
+    /// assert_eq!(chain.len(), 3);
+    ///
+    /// chain.retain(|&(a, b), word| a != "three" && b != "three" && word != "three");
+    /// assert_eq!(chain.words(("one", "two")), Some(&vec!["four"]));
+    /// assert_eq!(chain.len(), 1);
+    /// ```
+    pub fn retain(&mut self, mut f: impl FnMut(&Bigram<'a>, &str) -> bool) {
+        self.map.retain(|state, successors| {
+            successors.retain(|word| f(state, word));
+            !successors.is_empty()
+        });
+        let map = &self.map;
+        self.keys.retain(|state| map.contains_key(state));
+        self.starts.retain(|state| map.contains_key(state));
+        self.text_starts.retain(|state| map.contains_key(state));
+    }
+
+    /// Returns a new Markov chain with only the states reachable from
+    /// `start`.
+    ///
+    /// A state is reachable if a sequence of transitions leads from
+    /// `start` to it. Generating from `start` with the new chain
+    /// gives the same words as with the original chain, as long as
+    /// the generation does not run into a dead end: random jumps are
+    /// then limited to the reachable states. The new chain is empty
+    /// if `start` is not a state in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("a b c a b c");
+    /// chain.learn("x y z x y z");
+    /// let trimmed = chain.reachable_from(("a", "b"));
+    /// assert_eq!(chain.len(), 6);
+    /// assert_eq!(trimmed.len(), 3);
+    /// assert_eq!(trimmed.words(("x", "y")), None);
+    /// ```
+    pub fn reachable_from(&self, start: Bigram<'a>) -> MarkovChain<'a> {
+        let mut reachable = HashSet::new();
+        let mut stack = Vec::new();
+        if self.map.contains_key(&start) {
+            reachable.insert(start);
+            stack.push(start);
+        }
+        while let Some(state) = stack.pop() {
+            for &word in &self.map[&state] {
+                let next = (state.1, word);
+                if self.map.contains_key(&next) && reachable.insert(next) {
+                    stack.push(next);
+                }
+            }
+        }
+
+        let is_reachable = |state: &&Bigram<'a>| reachable.contains(*state);
+        MarkovChain {
+            map: self
+                .map
+                .iter()
+                .filter(|(state, _)| reachable.contains(*state))
+                .map(|(&state, words)| (state, words.clone()))
+                .collect(),
+            keys: self.keys.iter().filter(is_reachable).copied().collect(),
+            starts: self.starts.iter().filter(is_reachable).copied().collect(),
+            text_starts: self
+                .text_starts
+                .iter()
+                .filter(is_reachable)
+                .copied()
+                .collect(),
+            word_count: self.word_count,
+            sentence_count: self.sentence_count,
         }
-        // Sync the keys with the current map.
-        self.keys = self.map.keys().cloned().collect();
-        self.keys.sort_unstable();
     }
 
     /// Returs the number of states in the Markov chain.
@@ -138,6 +604,28 @@ impl<'a> MarkovChain<'a> {
         self.len() == 0
     }
 
+    /// Returns the number of states the Markov chain can hold without
+    /// reallocating.
+    ///
+    /// This is the capacity of the underlying hash map and it is
+    /// always at least [`len`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red orange yellow green blue indigo");
+    /// assert!(chain.capacity() >= chain.len());
+    /// ```
+    ///
+    /// [`len`]: struct.MarkovChain.html#method.len
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
     /// Get the possible words following the given bigram, or `None`
     /// if the state is invalid.
     ///
@@ -155,547 +643,8302 @@ impl<'a> MarkovChain<'a> {
         self.map.get(&state)
     }
 
-    /// Generate a sentence with `n` words of lorem ipsum text. The
-    /// sentence will start from a random point in the Markov chain
-    /// generated using the specified random number generator,
-    /// and a `.` will be added as necessary to form a full sentence.
+    /// Count how many times `word` was learned as a successor of
+    /// `state`.
     ///
-    /// See [`generate_with_rng_from`] if you want to control the
-    /// starting point for the generated text and see [`iter_with_rng`]
-    /// if you simply want a sequence of words.
+    /// The count determines how likely `word` is to be chosen after
+    /// `state`. Zero is returned if the state is unknown.
     ///
     /// # Examples
     ///
-    /// Generating the sounds of a grandfather clock:
-    ///
     /// ```
     /// use lipsum::MarkovChain;
-    /// use rand_chacha::ChaCha20Rng;
-    /// use rand::SeedableRng;
     ///
     /// let mut chain = MarkovChain::new();
-    /// chain.learn("Tick, Tock, Tick, Tock, Ding! Tick, Tock, Ding! Ding!");
-    /// println!("{}", chain.generate_with_rng(ChaCha20Rng::seed_from_u64(0), 15));
+    /// chain.learn("to be or not to be or to be");
+    /// assert_eq!(chain.count(("to", "be"), "or"), 2);
+    /// assert_eq!(chain.count(("be", "or"), "not"), 1);
+    /// assert_eq!(chain.count(("be", "or"), "to"), 1);
+    /// assert_eq!(chain.count(("foo", "bar"), "baz"), 0);
     /// ```
+    pub fn count(&self, state: Bigram<'a>, word: &str) -> usize {
+        match self.map.get(&state) {
+            Some(words) => words.iter().filter(|&&w| w == word).count(),
+            None => 0,
+        }
+    }
+
+    /// Iterate over all transitions in the Markov chain.
     ///
-    /// The output looks like this:
+    /// Each transition is a `(bigram, word)` pair, where `word` is a
+    /// possible successor of `bigram`. A transition which was learned
+    /// several times is produced several times. The bigrams are
+    /// produced in sorted order.
     ///
-    /// > Ding! Tick, Tock, Tick, Tock, Ding! Ding! Tock, Ding! Tick,
-    /// > Tock, Tick, Tock, Tick, Tock.
+    /// # Examples
     ///
-    /// [`generate_with_rng_from`]: struct.MarkovChain.html#method.generate_with_rng_from
-    /// [`iter_with_rng`]: struct.MarkovChain.html#method.iter_with_rng
-    pub fn generate_with_rng<R: Rng>(&self, rng: R, n: usize) -> String {
-        join_words(self.iter_with_rng(rng).take(n))
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red green blue red green yellow");
+    /// // Six words give four windows of three words.
+    /// assert_eq!(chain.edges().count(), 4);
+    /// assert_eq!(
+    ///     chain.edges().filter(|&(bigram, _)| bigram == ("red", "green")).count(),
+    ///     2
+    /// );
+    /// ```
+    pub fn edges(&self) -> impl Iterator<Item = (Bigram<'a>, &'a str)> + '_ {
+        self.keys
+            .iter()
+            .flat_map(move |&bigram| self.map[&bigram].iter().map(move |&word| (bigram, word)))
     }
 
-    /// Generate a sentence with `n` words of lorem ipsum text. The sentence
-    /// will start from a predetermined point in the Markov chain generated
-    /// using the default random number generator and a `.` will be added as
-    /// necessary to form a full sentence.
+    /// Write the transitions in the Markov chain as text.
     ///
-    /// See [`generate_from`] if you want to control the starting point for the
-    /// generated text and see [`iter`] if you simply want a sequence of words.
+    /// Each transition from [`edges`] is written on a line of its own
+    /// in the format `word1 word2 -> successor`. The text can be read
+    /// back with [`from_transitions_str`]. Sentence and paragraph
+    /// boundaries learned with [`learn_sentence`] and
+    /// [`learn_paragraphs`] are not included.
     ///
     /// # Examples
     ///
-    /// Generating the sounds of a grandfather clock:
-    ///
     /// ```
     /// use lipsum::MarkovChain;
     ///
     /// let mut chain = MarkovChain::new();
-    /// chain.learn("Tick, Tock, Tick, Tock, Ding! Tick, Tock, Ding! Ding!");
-    /// println!("{}", chain.generate(15));
+    /// chain.learn("red green blue red");
+    /// assert_eq!(
+    ///     chain.to_transitions_string(),
+    ///     "green blue -> red\nred green -> blue\n"
+    /// );
     /// ```
     ///
-    /// The output looks like this:
-    ///
-    /// > Ding! Tick, Tock, Tick, Tock, Ding! Ding! Tock, Ding! Tick,
-    /// > Tock, Tick, Tock, Tick, Tock.
-    ///
-    /// [`generate_from`]: struct.MarkovChain.html#method.generate_from
-    /// [`iter`]: struct.MarkovChain.html#method.iter
-    pub fn generate(&self, n: usize) -> String {
-        self.generate_with_rng(default_rng(), n)
+    /// [`edges`]: struct.MarkovChain.html#method.edges
+    /// [`from_transitions_str`]: struct.MarkovChain.html#method.from_transitions_str
+    /// [`learn_sentence`]: struct.MarkovChain.html#method.learn_sentence
+    /// [`learn_paragraphs`]: struct.MarkovChain.html#method.learn_paragraphs
+    pub fn to_transitions_string(&self) -> String {
+        let mut text = String::new();
+        for ((a, b), word) in self.edges() {
+            if [a, b, word]
+                .iter()
+                .any(|&w| w == SENTENCE_END || w == PARAGRAPH_BREAK)
+            {
+                continue;
+            }
+            text.push_str(&format!("{} {} -> {}\n", a, b, word));
+        }
+        text
     }
 
-    /// Generate a sentence with `n` words of lorem ipsum text. The
-    /// sentence will start from the given bigram and a `.` will be
-    /// added as necessary to form a full sentence.
+    /// Export the successor probabilities of each state as JSON.
     ///
-    /// Use [`generate_with_rng`] if the starting point is not important. See
-    /// [`iter_with_rng_from`] if you want a sequence of words that you can
-    /// format yourself.
+    /// The JSON is an array with an object for each state in sorted
+    /// order. The object has the two words of the state in a
+    /// `"bigram"` array and the probability of each distinct
+    /// successor in a `"successors"` object. The probability is the
+    /// number of times the successor was learned divided by the total
+    /// number of successors of the state, so the probabilities of a
+    /// state sum to 1. The end of sentences learned with
+    /// [`learn_sentence`] is written as an empty successor.
     ///
-    /// [`generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
-    /// [`iter_with_rng_from`]: struct.MarkovChain.html#method.iter_with_rng_from
-    pub fn generate_with_rng_from<R: Rng>(&self, rng: R, n: usize, from: Bigram<'a>) -> String {
-        join_words(self.iter_with_rng_from(rng, from).take(n))
-    }
-
-    /// Generate a sentence with `n` words of lorem ipsum text. The
-    /// sentence will start from the given bigram and a `.` will be
-    /// added as necessary to form a full sentence.
+    /// This is meant for analyzing and displaying the chain. The
+    /// chain cannot be read back from the JSON.
     ///
-    /// Use [`generate`] if the starting point is not important. See
-    /// [`iter_from`] if you want a sequence of words that you can
-    /// format yourself.
+    /// # Examples
     ///
-    /// [`generate`]: struct.MarkovChain.html#method.generate
-    /// [`iter_from`]: struct.MarkovChain.html#method.iter_from
-    pub fn generate_from(&self, n: usize, from: Bigram<'a>) -> String {
-        self.generate_with_rng_from(default_rng(), n, from)
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red green blue red green blue red green yellow");
+    /// assert!(chain.to_probabilities_json().starts_with(
+    ///     r#"[{"bigram":["blue","red"],"successors":{"green":1}},"#
+    /// ));
+    /// ```
+    ///
+    /// [`learn_sentence`]: struct.MarkovChain.html#method.learn_sentence
+    pub fn to_probabilities_json(&self) -> String {
+        let mut json = String::from("[");
+        for (i, &(a, b)) in self.keys.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"bigram\":[");
+            push_json_string(&mut json, a);
+            json.push(',');
+            push_json_string(&mut json, b);
+            json.push_str("],\"successors\":{");
+            let successors = &self.map[&(a, b)];
+            let total = successors.len() as f64;
+            for (j, (word, count)) in count_words(successors).into_iter().enumerate() {
+                if j > 0 {
+                    json.push(',');
+                }
+                push_json_string(&mut json, word);
+                json.push_str(&format!(":{}", f64::from(count) / total));
+            }
+            json.push_str("}}");
+        }
+        json.push(']');
+        json
     }
 
-    /// Make a never-ending iterator over the words in the Markov
-    /// chain. The iterator starts at a random point in the chain.
-    pub fn iter_with_rng<R: Rng>(&self, mut rng: R) -> Words<'_, R> {
-        let initial_bigram = if self.is_empty() {
-            ("", "")
+    /// Create a Markov chain from transitions written as text.
+    ///
+    /// Each line must have the format `word1 word2 -> successor`,
+    /// which adds a transition from the bigram `(word1, word2)` to
+    /// `successor`. Repeated lines add the transition several times.
+    /// Empty lines are ignored. This is the format written by
+    /// [`to_transitions_string`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChainError::Parse`] with the line number (starting
+    /// from 1) of the first line which does not have the right
+    /// format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{ChainError, MarkovChain};
+    ///
+    /// let chain = MarkovChain::from_transitions_str("red green -> blue\n").unwrap();
+    /// assert_eq!(chain.words(("red", "green")), Some(&vec!["blue"]));
+    ///
+    /// let error = MarkovChain::from_transitions_str("red green -> blue\nyellow\n");
+    /// assert_eq!(error.unwrap_err(), ChainError::Parse { line: 2 });
+    /// ```
+    ///
+    /// [`to_transitions_string`]: struct.MarkovChain.html#method.to_transitions_string
+    /// [`ChainError::Parse`]: enum.ChainError.html#variant.Parse
+    pub fn from_transitions_str(text: &'a str) -> Result<MarkovChain<'a>, ChainError> {
+        let mut chain = MarkovChain::new();
+        for (idx, line) in text.lines().enumerate() {
+            let tokens = line.split_whitespace().collect::<Vec<_>>();
+            match tokens[..] {
+                [] => continue,
+                [a, b, "->", word] => {
+                    chain.word_count += 1;
+                    if word.ends_with(TERMINATORS) {
+                        chain.sentence_count += 1;
+                    }
+                    match chain.map.entry((a, b)) {
+                        Entry::Occupied(mut entry) => entry.get_mut().push(word),
+                        Entry::Vacant(entry) => {
+                            chain.keys.push((a, b));
+                            entry.insert(vec![word]);
+                        }
+                    }
+                }
+                _ => return Err(ChainError::Parse { line: idx + 1 }),
+            }
+        }
+        chain.keys.sort_unstable();
+        Ok(chain)
+    }
+
+    /// Read the text in a file for learning.
+    ///
+    /// The chain borrows the words it learns, so the text is returned
+    /// as an [`OwnedCorpus`] which owns it. Learn the chain once with
+    /// [`OwnedCorpus::chain`] and keep it for as long as the corpus.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error if the file cannot be read.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use lipsum::MarkovChain;
+    ///
+    /// let corpus = MarkovChain::from_file("corpus.txt").unwrap();
+    /// let chain = corpus.chain();
+    /// println!("{}", chain.generate(20));
+    /// ```
+    ///
+    /// [`OwnedCorpus`]: struct.OwnedCorpus.html
+    /// [`OwnedCorpus::chain`]: struct.OwnedCorpus.html#method.chain
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<OwnedCorpus> {
+        Ok(OwnedCorpus::new(fs::read_to_string(path)?))
+    }
+
+    /// Returns the bigrams which start a sentence.
+    ///
+    /// These are the first two words of each learned text and the
+    /// first two words following a word which ends a sentence. Only
+    /// bigrams which are states in the chain are included. This is
+    /// useful for finding natural starting points for
+    /// [`generate_from`]. The bigrams are sorted and have no
+    /// duplicates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("The cat sat down. A dog barked loudly.");
+    /// assert_eq!(chain.sentence_starts(), vec![("A", "dog"), ("The", "cat")]);
+    /// ```
+    ///
+    /// [`generate_from`]: struct.MarkovChain.html#method.generate_from
+    pub fn sentence_starts(&self) -> Vec<Bigram<'a>> {
+        let mut starts = self.text_starts.clone();
+        starts.extend_from_slice(&self.starts);
+        for (&(a, b), successors) in &self.map {
+            if (a.ends_with(TERMINATORS) || a == PARAGRAPH_BREAK) && b != PARAGRAPH_BREAK {
+                starts.extend(successors.iter().map(|&c| (b, c)));
+            }
+        }
+        starts.retain(|state| self.map.contains_key(state));
+        starts.sort_unstable();
+        starts.dedup();
+        starts
+    }
+
+    /// Returns the number of distinct words seen by the Markov chain.
+    ///
+    /// This is the vocabulary size of the learned text. It is computed
+    /// by scanning all states and their possible following words.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// assert_eq!(chain.distinct_words(), 0);
+    ///
+    /// chain.learn("red green red blue");
+    /// assert_eq!(chain.distinct_words(), 3);
+    /// ```
+    pub fn distinct_words(&self) -> usize {
+        let mut words = HashSet::new();
+        for (&(a, b), successors) in &self.map {
+            words.insert(a);
+            words.insert(b);
+            words.extend(successors);
+        }
+        words.remove(SENTENCE_END);
+        words.remove(PARAGRAPH_BREAK);
+        words.len()
+    }
+
+    /// Compute summary statistics of the Markov chain.
+    ///
+    /// The statistics are computed in a single pass over the states.
+    /// The averages are `0.0` for an empty chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red green blue red green yellow");
+    /// let stats = chain.stats();
+    /// assert_eq!(stats.states, 3);
+    /// assert_eq!(stats.tokens, 6);
+    /// assert_eq!(stats.distinct_words, 4);
+    /// assert_eq!(stats.avg_word_len, 4.5);
+    /// assert_eq!(stats.avg_successors, 4.0 / 3.0);
+    /// assert_eq!(stats.dead_ends, 1);
+    /// ```
+    pub fn stats(&self) -> ChainStats {
+        let mut words = HashSet::new();
+        let mut dead_ends = HashSet::new();
+        let mut successor_count = 0;
+        for (&(a, b), successors) in &self.map {
+            words.insert(a);
+            words.insert(b);
+            words.extend(successors);
+            successor_count += count_words(successors).len();
+            for &word in successors {
+                if word != SENTENCE_END && !self.map.contains_key(&(b, word)) {
+                    dead_ends.insert((b, word));
+                }
+            }
+        }
+        words.remove(SENTENCE_END);
+        words.remove(PARAGRAPH_BREAK);
+
+        let average = |total: usize, count: usize| {
+            if count == 0 {
+                0.0
+            } else {
+                total as f64 / count as f64
+            }
+        };
+        let word_chars = words.iter().map(|word| word.chars().count()).sum();
+        ChainStats {
+            states: self.len(),
+            tokens: self.word_count,
+            distinct_words: words.len(),
+            avg_word_len: average(word_chars, words.len()),
+            avg_successors: average(successor_count, self.len()),
+            dead_ends: dead_ends.len(),
+        }
+    }
+
+    /// Returns the fraction of states with more than one distinct
+    /// successor.
+    ///
+    /// This measures how much the generated text can deviate from the
+    /// learned text: a chain with a diversity of `0.0` can only
+    /// reproduce the learned text verbatim, since every state has a
+    /// single possible successor. An empty chain has a diversity of
+    /// `0.0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("a b c d e");
+    /// assert_eq!(chain.diversity(), 0.0);
+    /// chain.learn("a b x");
+    /// // The state ("a", "b") now has two successors.
+    /// assert_eq!(chain.diversity(), 1.0 / 3.0);
+    /// ```
+    pub fn diversity(&self) -> f64 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let branching = self
+            .map
+            .values()
+            .filter(|words| words.iter().any(|&word| word != words[0]))
+            .count();
+        branching as f64 / self.len() as f64
+    }
+
+    /// Estimate the number of sentences in `n` generated words.
+    ///
+    /// This is only an estimate based on the average sentence length
+    /// in the learned text: `learn` counts the words and the words
+    /// ending with `.`, `!`, or `?`. If the learned text has no such
+    /// words, the generated text will consist of a single sentence
+    /// and the estimate is `1.0` for any positive `n`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("One two three four. Five six! Seven eight nine ten?");
+    /// // The average sentence length is 10 / 3 words.
+    /// assert_eq!(chain.estimate_sentences(20), 6.0);
+    /// ```
+    pub fn estimate_sentences(&self, n: usize) -> f64 {
+        if n == 0 {
+            0.0
+        } else if self.sentence_count == 0 {
+            1.0
         } else {
-            *self.keys.choose(&mut rng).unwrap()
+            n as f64 * self.sentence_count as f64 / self.word_count as f64
+        }
+    }
+
+    /// Returns `true` if a word ending a sentence can be reached from
+    /// the given bigram.
+    ///
+    /// This follows the transitions in the Markov chain, starting from
+    /// `from`, and checks if a word ending with `.`, `!`, or `?` can
+    /// be generated before the chain runs into a dead end and has to
+    /// jump to a random state. This is useful for detecting text which
+    /// never produces clean sentence endings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red orange yellow. green blue indigo");
+    /// assert!(chain.can_terminate(("red", "orange")));
+    /// assert!(!chain.can_terminate(("green", "blue")));
+    /// ```
+    pub fn can_terminate(&self, from: Bigram<'a>) -> bool {
+        let mut seen = HashSet::new();
+        let mut stack = vec![from];
+        while let Some(state) = stack.pop() {
+            if !seen.insert(state) {
+                continue;
+            }
+            if let Some(successors) = self.map.get(&state) {
+                for &word in successors {
+                    if word.ends_with(TERMINATORS) {
+                        return true;
+                    }
+                    stack.push((state.1, word));
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns the dead ends in the Markov chain in sorted order.
+    ///
+    /// A dead end is a bigram which can be reached by a transition,
+    /// but which has no successors itself. This happens for the last
+    /// bigram of a learned text unless the bigram occurs elsewhere.
+    /// When the generation reaches a dead end, it continues from a
+    /// random point in the chain. Sentence ends learned with
+    /// [`learn_sentence`] are not dead ends.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red green blue");
+    /// assert_eq!(chain.dead_ends(), vec![("green", "blue")]);
+    ///
+    /// chain.learn("green blue red");
+    /// assert_eq!(chain.dead_ends(), vec![("blue", "red")]);
+    /// ```
+    ///
+    /// [`learn_sentence`]: struct.MarkovChain.html#method.learn_sentence
+    pub fn dead_ends(&self) -> Vec<Bigram<'a>> {
+        let mut dead_ends = self
+            .edges()
+            .filter(|&(_, word)| word != SENTENCE_END)
+            .map(|((_, b), word)| (b, word))
+            .filter(|state| !self.map.contains_key(state))
+            .collect::<Vec<_>>();
+        dead_ends.sort_unstable();
+        dead_ends.dedup();
+        dead_ends
+    }
+
+    /// Returns the bigram which occurs most often.
+    ///
+    /// Like in [`sample_bigram_weighted`], a bigram is counted each
+    /// time it was followed by another word in the learned text. Ties
+    /// are broken by picking the first bigram in sort order. Returns
+    /// `None` if the chain is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("the cat sat on the cat mat and the cat ran");
+    /// assert_eq!(chain.most_common_bigram(), Some(("the", "cat")));
+    /// assert_eq!(MarkovChain::new().most_common_bigram(), None);
+    /// ```
+    ///
+    /// [`sample_bigram_weighted`]: struct.MarkovChain.html#method.sample_bigram_weighted
+    pub fn most_common_bigram(&self) -> Option<Bigram<'a>> {
+        self.keys
+            .iter()
+            .min_by_key(|key| std::cmp::Reverse(self.map[key].len()))
+            .copied()
+    }
+
+    /// Returns the bigram with the most distinct successors.
+    ///
+    /// Unlike [`most_common_bigram`], repeated successors are only
+    /// counted once. Ties are broken by picking the first bigram in
+    /// sort order. Returns `None` if the chain is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red green blue red green blue red green blue red green blue");
+    /// chain.learn_all(&["one two three", "one two four", "one two five"]);
+    /// assert_eq!(chain.most_common_bigram(), Some(("red", "green")));
+    /// assert_eq!(chain.most_diverse_bigram(), Some(("one", "two")));
+    /// ```
+    ///
+    /// [`most_common_bigram`]: struct.MarkovChain.html#method.most_common_bigram
+    pub fn most_diverse_bigram(&self) -> Option<Bigram<'a>> {
+        self.keys
+            .iter()
+            .min_by_key(|key| std::cmp::Reverse(count_words(&self.map[key]).len()))
+            .copied()
+    }
+
+    /// Choose a random bigram, weighted by how often it occurs.
+    ///
+    /// Each bigram is weighted by the number of times it was followed
+    /// by another word in the learned text. Frequent bigrams are thus
+    /// chosen more often than rare bigrams. This is useful as a
+    /// starting point when building custom generation loops with
+    /// [`iter_with_rng_from`]. Returns `None` if the chain is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("the cat sat on the cat mat");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// assert!(chain.sample_bigram_weighted(rng).is_some());
+    ///
+    /// let empty = MarkovChain::new();
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// assert_eq!(empty.sample_bigram_weighted(rng), None);
+    /// ```
+    ///
+    /// [`iter_with_rng_from`]: struct.MarkovChain.html#method.iter_with_rng_from
+    #[cfg(feature = "rand")]
+    pub fn sample_bigram_weighted<R: Rng>(&self, mut rng: R) -> Option<Bigram<'a>> {
+        self.keys
+            .choose_weighted(&mut rng, |state| self.map[state].len())
+            .ok()
+            .copied()
+    }
+
+    /// Compare the transitions in this chain with another chain.
+    ///
+    /// The states are compared one by one and the result lists the
+    /// bigrams only found in one of the chains as well as the bigrams
+    /// found in both chains, but with different successors. The
+    /// successors differ if a word follows the bigram in one chain
+    /// but not in the other, or if it follows it a different number
+    /// of times. All lists are sorted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut old = MarkovChain::new();
+    /// old.learn("one two three four");
+    ///
+    /// let mut new = MarkovChain::new();
+    /// new.learn("one two three five six");
+    ///
+    /// let diff = old.diff(&new);
+    /// assert_eq!(diff.only_in_self, vec![]);
+    /// assert_eq!(diff.only_in_other, vec![("three", "five")]);
+    /// assert_eq!(diff.changed, vec![("two", "three")]);
+    /// ```
+    pub fn diff(&self, other: &MarkovChain<'a>) -> ChainDiff<'a> {
+        let sorted = |words: &[&'a str]| {
+            let mut words = words.to_vec();
+            words.sort_unstable();
+            words
+        };
+
+        let mut diff = ChainDiff::default();
+        for state in &self.keys {
+            match other.map.get(state) {
+                Some(successors) => {
+                    if sorted(&self.map[state]) != sorted(successors) {
+                        diff.changed.push(*state);
+                    }
+                }
+                None => diff.only_in_self.push(*state),
+            }
+        }
+        for state in &other.keys {
+            if !self.map.contains_key(state) {
+                diff.only_in_other.push(*state);
+            }
+        }
+        diff
+    }
+
+    /// Check the internal consistency of the Markov chain.
+    ///
+    /// This verifies that the states are kept sorted, that the sorted
+    /// states match the states in the transition map, and that every
+    /// state has at least one successor. A chain built with the
+    /// methods on this type always passes the checks, but this is a
+    /// useful sanity check after loading a chain from elsewhere.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red orange yellow green blue indigo");
+    /// assert_eq!(chain.validate(), Ok(()));
+    /// ```
+    pub fn validate(&self) -> Result<(), ChainError> {
+        if self.keys.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(ChainError::UnsortedKeys);
+        }
+        if self.keys.len() != self.map.len()
+            || self.keys.iter().any(|state| !self.map.contains_key(state))
+        {
+            return Err(ChainError::InconsistentKeys);
+        }
+        if self.map.values().any(|successors| successors.is_empty()) {
+            return Err(ChainError::EmptySuccessors);
+        }
+        Ok(())
+    }
+
+    /// Compute a fingerprint of the Markov chain.
+    ///
+    /// The fingerprint is a 64-bit hash over all transitions and
+    /// sentence starts in the chain. It only depends on which
+    /// transitions and starts have been learned and how often, not on
+    /// the order in which the text was learned. Equal chains thus
+    /// have the same fingerprint. The hash is computed with the
+    /// FNV-1a algorithm and is stable across program runs, which
+    /// makes it suitable as a cache key.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut first = MarkovChain::new();
+    /// first.learn("one two three");
+    /// first.learn("four five six");
+    ///
+    /// let mut second = MarkovChain::new();
+    /// second.learn("four five six");
+    /// second.learn("one two three");
+    ///
+    /// assert_eq!(first.fingerprint(), second.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        let mut successors = Vec::new();
+        // The keys are sorted, so only the successors need sorting.
+        for state in self.keys.iter() {
+            successors.clear();
+            successors.extend_from_slice(&self.map[state]);
+            successors.sort_unstable();
+
+            hasher.write_str(state.0);
+            hasher.write_str(state.1);
+            hasher.write(&(successors.len() as u64).to_le_bytes());
+            for word in &successors {
+                hasher.write_str(word);
+            }
+        }
+
+        for state in &sorted_starts(self) {
+            hasher.write_str(state.0);
+            hasher.write_str(state.1);
+        }
+        hasher.finish()
+    }
+
+    /// Generate a sentence with `n` words of lorem ipsum text. The
+    /// sentence will start from a random point in the Markov chain
+    /// generated using the specified random number generator,
+    /// and a `.` will be added as necessary to form a full sentence.
+    ///
+    /// See [`generate_with_rng_from`] if you want to control the
+    /// starting point for the generated text and see [`iter_with_rng`]
+    /// if you simply want a sequence of words.
+    ///
+    /// # Examples
+    ///
+    /// Generating the sounds of a grandfather clock:
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand_chacha::ChaCha20Rng;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("Tick, Tock, Tick, Tock, Ding! Tick, Tock, Ding! Ding!");
+    /// println!("{}", chain.generate_with_rng(ChaCha20Rng::seed_from_u64(0), 15));
+    /// ```
+    ///
+    /// The output looks like this:
+    ///
+    /// > Ding! Tick, Tock, Tick, Tock, Ding! Ding! Tock, Ding! Tick,
+    /// > Tock, Tick, Tock, Tick, Tock.
+    ///
+    /// [`generate_with_rng_from`]: struct.MarkovChain.html#method.generate_with_rng_from
+    /// [`iter_with_rng`]: struct.MarkovChain.html#method.iter_with_rng
+    #[cfg(feature = "rand")]
+    pub fn generate_with_rng<R: Rng>(&self, rng: R, n: usize) -> String {
+        self.generate_with_options(rng, n, &Options::new())
+    }
+
+    /// Generate a sentence with `n` words of lorem ipsum text and
+    /// write it to `writer`.
+    ///
+    /// The text is identical to the text returned by
+    /// [`generate_with_rng`], but it is written incrementally. Only a
+    /// small buffer is kept in memory, which makes this suitable for
+    /// generating very large amounts of text. Wrap the writer in a
+    /// [`BufWriter`] if it is unbuffered.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from writing to `writer`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand_chacha::ChaCha20Rng;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("Tick, Tock, Tick, Tock, Ding! Tick, Tock, Ding! Ding!");
+    ///
+    /// let mut stdout = std::io::stdout();
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// chain.generate_to_writer(rng, 15, &mut stdout).unwrap();
+    /// ```
+    ///
+    /// [`generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
+    /// [`BufWriter`]: https://doc.rust-lang.org/std/io/struct.BufWriter.html
+    #[cfg(feature = "rand")]
+    pub fn generate_to_writer<R: Rng, W: Write>(
+        &self,
+        rng: R,
+        n: usize,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        for word in self.iter_with_rng(rng).take(n) {
+            builder.push_word(word);
+            if builder.text.len() >= WRITER_BUFFER_SIZE {
+                // Keep the last word since punctuation might be
+                // trimmed from it when the sentence ends.
+                if let Some(idx) = builder.text.rfind(' ') {
+                    writer.write_all(&builder.text.as_bytes()[..idx])?;
+                    builder.text.drain(..idx);
+                }
+            }
+        }
+        writer.write_all(builder.finish().as_bytes())
+    }
+
+    /// Generate a sentence with `n` words of lorem ipsum text into
+    /// `buf`.
+    ///
+    /// This works like [`generate_with_rng`], except that the text is
+    /// written into an existing buffer. The buffer is cleared first,
+    /// but its allocation is reused. This avoids allocating a new
+    /// `String` when generating text repeatedly in a loop.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand_chacha::ChaCha20Rng;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("Tick, Tock, Tick, Tock, Ding! Tick, Tock, Ding! Ding!");
+    ///
+    /// let mut buf = String::new();
+    /// for seed in 0..3 {
+    ///     chain.generate_into(ChaCha20Rng::seed_from_u64(seed), 5, &mut buf);
+    ///     println!("{}", buf);
+    /// }
+    /// ```
+    ///
+    /// [`generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
+    #[cfg(feature = "rand")]
+    pub fn generate_into<R: Rng>(&self, rng: R, n: usize, buf: &mut String) {
+        let options = Options::new();
+        let mut builder = TextBuilder::with_buffer(&options, std::mem::take(buf));
+        for word in self.iter_with_options(rng, &options).take(n) {
+            builder.push_word(word);
+        }
+        *buf = builder.finish();
+    }
+
+    /// Generate a sentence with `n` words of lorem ipsum text. The sentence
+    /// will start from a predetermined point in the Markov chain generated
+    /// using the default random number generator and a `.` will be added as
+    /// necessary to form a full sentence.
+    ///
+    /// See [`generate_from`] if you want to control the starting point for the
+    /// generated text and see [`iter`] if you simply want a sequence of words.
+    ///
+    /// # Examples
+    ///
+    /// Generating the sounds of a grandfather clock:
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("Tick, Tock, Tick, Tock, Ding! Tick, Tock, Ding! Ding!");
+    /// println!("{}", chain.generate(15));
+    /// ```
+    ///
+    /// The output looks like this:
+    ///
+    /// > Ding! Tick, Tock, Tick, Tock, Ding! Ding! Tock, Ding! Tick,
+    /// > Tock, Tick, Tock, Tick, Tock.
+    ///
+    /// [`generate_from`]: struct.MarkovChain.html#method.generate_from
+    /// [`iter`]: struct.MarkovChain.html#method.iter
+    #[cfg(feature = "rand")]
+    pub fn generate(&self, n: usize) -> String {
+        self.generate_with_rng(default_rng(), n)
+    }
+
+    /// Generate a sentence with `n` words of lorem ipsum text. The
+    /// sentence will start from the given bigram and a `.` will be
+    /// added as necessary to form a full sentence.
+    ///
+    /// Use [`generate_with_rng`] if the starting point is not important. See
+    /// [`iter_with_rng_from`] if you want a sequence of words that you can
+    /// format yourself.
+    ///
+    /// [`generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
+    /// [`iter_with_rng_from`]: struct.MarkovChain.html#method.iter_with_rng_from
+    #[cfg(feature = "rand")]
+    pub fn generate_with_rng_from<R: Rng>(&self, rng: R, n: usize, from: Bigram<'a>) -> String {
+        join_words(self.iter_with_rng_from(rng, from).take(n), &Options::new())
+    }
+
+    /// Generate a sentence with `n` words of lorem ipsum text. The
+    /// sentence will start from the given bigram and a `.` will be
+    /// added as necessary to form a full sentence.
+    ///
+    /// Use [`generate`] if the starting point is not important. See
+    /// [`iter_from`] if you want a sequence of words that you can
+    /// format yourself.
+    ///
+    /// [`generate`]: struct.MarkovChain.html#method.generate
+    /// [`iter_from`]: struct.MarkovChain.html#method.iter_from
+    pub fn generate_from(&self, n: usize, from: Bigram<'a>) -> String {
+        join_words(self.iter_from(from).take(n), &Options::new())
+    }
+
+    /// Generate `n` words of lorem ipsum text using the given
+    /// options.
+    ///
+    /// This works like [`generate_with_rng`], but the [`Options`]
+    /// allow you to control how the words are joined into sentences.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red orange yellow green blue indigo violet");
+    /// let options = Options::new().max_sentence_chars(20);
+    /// let rng = ChaCha20Rng::seed_from_u64(1);
+    /// assert_eq!(
+    ///     chain.generate_with_options(rng, 6, &options),
+    ///     "Green blue indigo. Yellow green blue."
+    /// );
+    /// ```
+    ///
+    /// [`generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
+    /// [`Options`]: struct.Options.html
+    #[cfg(feature = "rand")]
+    pub fn generate_with_options<R: Rng>(&self, rng: R, n: usize, options: &Options) -> String {
+        let mut words = self.iter_with_options(rng, options);
+        let mut builder = TextBuilder::new(options);
+        // Stripped numbers are not counted as words. Stop if the
+        // chain produces nothing else.
+        let mut idle = 0;
+        while builder.words < n && idle <= self.len() {
+            let count = builder.words;
+            match words.next() {
+                Some(word) => {
+                    if words.after_paragraph_break() {
+                        builder.push_word(PARAGRAPH_BREAK);
+                    }
+                    builder.push_word_with_rng(word, &mut words.rng);
+                }
+                None => break,
+            }
+            idle = if builder.words == count { idle + 1 } else { 0 };
+        }
+        builder.finish()
+    }
+
+    /// Generate `n` words of lorem ipsum text, selecting words with
+    /// `selector`.
+    ///
+    /// This works like [`generate_with_rng`], except that the
+    /// successor of each bigram is selected by the [`Selector`].
+    /// Random jumps at dead ends still use `rng` directly. Use
+    /// [`FrequencySelector`] to select words according to their
+    /// frequency like [`generate_with_rng`] does.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{FrequencySelector, MarkovChain};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red green blue red green yellow red green blue");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// println!("{}", chain.generate_with_selector(rng, 10, FrequencySelector));
+    /// ```
+    ///
+    /// [`generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
+    /// [`Selector`]: trait.Selector.html
+    /// [`FrequencySelector`]: struct.FrequencySelector.html
+    #[cfg(feature = "rand")]
+    pub fn generate_with_selector<R: Rng, S: Selector>(
+        &self,
+        rng: R,
+        n: usize,
+        mut selector: S,
+    ) -> String {
+        let mut words = self.iter_with_rng(rng);
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        while builder.words < n {
+            let word = words.step(|words, candidates| {
+                selector.choose(&count_words(candidates), &mut words.rng)
+            });
+            match word {
+                Some(word) => builder.push_word(word),
+                None => break,
+            }
+        }
+        builder.finish()
+    }
+
+    /// Generate `n` words of lorem ipsum text, where the words are
+    /// selected with a bias based on their length.
+    ///
+    /// This is a shorthand for [`generate_with_options`] with the
+    /// [`Options::word_length_bias`] set to `bias`. See
+    /// [`WordLengthBias`] for how the words are weighted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, WordLengthBias};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("a b c a b circumstantial a b d a b e");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// println!("{}", chain.generate_biased(rng, 20, WordLengthBias::Longer));
+    /// ```
+    ///
+    /// [`generate_with_options`]: struct.MarkovChain.html#method.generate_with_options
+    /// [`Options::word_length_bias`]: struct.Options.html#structfield.word_length_bias
+    /// [`WordLengthBias`]: enum.WordLengthBias.html
+    #[cfg(feature = "rand")]
+    pub fn generate_biased<R: Rng>(&self, rng: R, n: usize, bias: WordLengthBias) -> String {
+        let options = Options::new().word_length_bias(bias);
+        self.generate_with_options(rng, n, &options)
+    }
+
+    /// Generate `sentences` sentences which all start with `prefix`.
+    ///
+    /// After the prefix, each sentence continues from the bigram
+    /// formed by the last two words of `prefix` and runs until the
+    /// chain produces a word ending with `.`, `!`, or `?`. If the
+    /// prefix has fewer than two words or if the bigram is unknown to
+    /// the Markov chain, the prefix is still added and the sentence
+    /// continues from a random point in the chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("Our product is fast. Our product is cheap. It is green!");
+    /// let rng = ChaCha20Rng::seed_from_u64(1);
+    /// assert_eq!(
+    ///     chain.generate_with_sentence_prefix(rng, 2, "Our product"),
+    ///     "Our product is cheap. Our product is fast."
+    /// );
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn generate_with_sentence_prefix<R: Rng>(
+        &self,
+        rng: R,
+        sentences: usize,
+        prefix: &str,
+    ) -> String {
+        let prefix = prefix.split_whitespace().collect::<Vec<_>>();
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        let mut words = self.iter_with_rng(rng);
+        for _ in 0..sentences {
+            for word in &prefix {
+                builder.push_word(word);
+            }
+
+            words.skip_prefix(&prefix);
+            words.push_sentence(&mut builder);
+        }
+        builder.finish()
+    }
+
+    /// Generate `n` words of lorem ipsum text which include each of
+    /// the `keywords` at least once.
+    ///
+    /// The text is generated like [`generate_with_rng`] while keeping
+    /// track of which keywords have been generated, ignoring case and
+    /// punctuation. When the words left are just enough for the
+    /// missing keywords, the missing keywords are added in order.
+    /// They are capitalized and spaced like any other word.
+    ///
+    /// This is best-effort: the keywords are simply appended, so they
+    /// might not fit the surrounding text. If there are more keywords
+    /// than `n`, the text will have more than `n` words.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red green blue red green yellow");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// let text = chain.generate_with_keywords(rng, 6, &["cheap", "fast"]);
+    /// assert!(text.ends_with("cheap fast."));
+    /// ```
+    ///
+    /// [`generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
+    #[cfg(feature = "rand")]
+    pub fn generate_with_keywords<R: Rng>(&self, rng: R, n: usize, keywords: &[&str]) -> String {
+        let mut missing: Vec<(&str, String)> = Vec::with_capacity(keywords.len());
+        for &keyword in keywords {
+            let lowercase = keyword.to_lowercase();
+            if !missing.iter().any(|(_, other)| *other == lowercase) {
+                missing.push((keyword, lowercase));
+            }
+        }
+
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        let mut words = self.iter_with_rng(rng);
+        while builder.words + missing.len() < n {
+            let word = match words.next() {
+                Some(word) => word,
+                None => break,
+            };
+            builder.push_word(word);
+            let bare = word.trim_matches(is_ascii_punctuation).to_lowercase();
+            missing.retain(|(_, lowercase)| *lowercase != bare);
+        }
+        for (keyword, _) in missing {
+            builder.push_word(keyword);
+        }
+        builder.finish()
+    }
+
+    /// Generate text with the same sentence lengths as `template`.
+    ///
+    /// The template is split into sentences after words ending with
+    /// `.`, `!`, or `?`, and the words of each sentence are counted.
+    /// Words left after the last terminator form a final sentence.
+    /// The generated text has the same number of sentences with the
+    /// same number of words, filled with words from the chain. To
+    /// keep the lengths, terminators generated inside a sentence are
+    /// removed and each sentence is ended explicitly. Words which
+    /// are only punctuation are skipped. If the chain produces
+    /// nothing else, the text is cut short.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red orange yellow. green blue! indigo violet red green yellow");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// let text = chain.generate_like(rng, "Hello there! How are you today?");
+    /// let lengths = text
+    ///     .split_terminator(&['.', '!', '?'][..])
+    ///     .map(|sentence| sentence.split_whitespace().count())
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(lengths, [2, 4]);
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn generate_like<R: Rng>(&self, rng: R, template: &str) -> String {
+        let mut lengths = Vec::new();
+        let mut sentence_words = 0;
+        for word in template.split_whitespace() {
+            sentence_words += 1;
+            if word.ends_with(TERMINATORS) {
+                lengths.push(sentence_words);
+                sentence_words = 0;
+            }
+        }
+        if sentence_words > 0 {
+            lengths.push(sentence_words);
+        }
+
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        let mut words = self.iter_with_rng(rng);
+        let mut idle = 0;
+        for length in lengths {
+            let mut remaining = length;
+            while remaining > 0 {
+                let word = match words.next() {
+                    Some(word) if idle <= self.len() => word,
+                    _ => return builder.finish(),
+                };
+                let word = if remaining > 1 && word.ends_with(TERMINATORS) {
+                    word.trim_end_matches(is_ascii_punctuation)
+                } else {
+                    word
+                };
+                if word.is_empty() {
+                    idle += 1;
+                    continue;
+                }
+                idle = 0;
+                builder.push_word(word);
+                remaining -= 1;
+            }
+            builder.end_sentence();
+        }
+        builder.finish()
+    }
+
+    /// Generate `n` words of lorem ipsum text which end with a
+    /// question.
+    ///
+    /// The text is generated like [`generate_with_rng`], but the last
+    /// sentence ends with `?` instead of `.` or any other trailing
+    /// punctuation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("Do you like green eggs and ham? I do not like them.");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// let text = chain.generate_ending_with_question(rng, 10);
+    /// assert!(text.ends_with('?'));
+    /// ```
+    ///
+    /// [`generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
+    #[cfg(feature = "rand")]
+    pub fn generate_ending_with_question<R: Rng>(&self, rng: R, n: usize) -> String {
+        let mut text = self.generate_with_rng(rng, n);
+        if !text.is_empty() {
+            let idx = text.trim_end_matches(is_ascii_punctuation).len();
+            text.truncate(idx);
+            text.push('?');
+        }
+        text
+    }
+
+    /// Generate `n` words of lorem ipsum text starting with a word
+    /// which is capitalized in the learned text.
+    ///
+    /// The first bigram is chosen among the states whose first word
+    /// starts with an uppercase letter. Such words are typically
+    /// proper nouns or sentence starts, which makes for a natural
+    /// lead. If there are no such states, this works like
+    /// [`generate_with_rng`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("the cat met Alice and the dog met the bird");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// assert_eq!(chain.generate_proper_start(rng, 4), "Alice and the dog.");
+    /// ```
+    ///
+    /// [`generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
+    #[cfg(feature = "rand")]
+    pub fn generate_proper_start<R: Rng>(&self, mut rng: R, n: usize) -> String {
+        let capitalized = self
+            .keys
+            .iter()
+            .filter(|(word, _)| word.starts_with(char::is_uppercase))
+            .collect::<Vec<_>>();
+        match capitalized.choose(&mut rng) {
+            Some(&&from) => self.generate_with_rng_from(rng, n, from),
+            None => self.generate_with_rng(rng, n),
+        }
+    }
+
+    /// Generate `n` words of lorem ipsum text starting from the
+    /// bigram with the most distinct successors.
+    ///
+    /// The text starts at the [`most_diverse_bigram`], which gives
+    /// the chain the most choices right away. This is useful for
+    /// uneven texts where a random start often lands in a long
+    /// repetitive stretch. An empty chain generates an empty string.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red green blue red green blue red green blue red green blue");
+    /// chain.learn_all(&["one two three", "one two four", "one two five"]);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// let text = chain.generate_from_most_diverse(rng, 5);
+    /// assert!(text.starts_with("One two"));
+    /// ```
+    ///
+    /// [`most_diverse_bigram`]: struct.MarkovChain.html#method.most_diverse_bigram
+    #[cfg(feature = "rand")]
+    pub fn generate_from_most_diverse<R: Rng>(&self, rng: R, n: usize) -> String {
+        match self.most_diverse_bigram() {
+            Some(from) => self.generate_with_rng_from(rng, n, from),
+            None => String::new(),
+        }
+    }
+
+    /// Generate the most likely sentence with `n` words, starting
+    /// from the given bigram.
+    ///
+    /// Unlike the other generation methods, no randomness is
+    /// involved: the most frequent successor is selected at each
+    /// step, with ties broken by picking the first word in sort
+    /// order. If the chain runs into a dead end, it continues from
+    /// the first bigram in sort order. This is useful for producing
+    /// reproducible representative samples of the learned text and
+    /// for debugging.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("the cat sat. the cat ran. the cat sat.");
+    /// assert_eq!(chain.generate_greedy(6, ("the", "cat")), "The cat sat. The cat sat.");
+    /// ```
+    pub fn generate_greedy(&self, n: usize, from: Bigram<'a>) -> String {
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        for word in self.iter_deterministic(from).take(n) {
+            builder.push_word(word);
+        }
+        builder.finish()
+    }
+
+    /// Generate `sentences` sentences with a refrain repeated after
+    /// every `every` sentences.
+    ///
+    /// The refrain is added as a sentence of its own: it is
+    /// capitalized and ends with a `'.'` unless it already ends with
+    /// a sentence terminator. No refrain is added if `every` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("The wind blows. The rain falls. The sun shines.");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// println!("{}", chain.generate_with_refrain(rng, 4, "oh the weather", 2));
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn generate_with_refrain<R: Rng>(
+        &self,
+        rng: R,
+        sentences: usize,
+        refrain: &str,
+        every: usize,
+    ) -> String {
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        if self.is_empty() {
+            return builder.finish();
+        }
+
+        let mut words = self.iter_with_rng(rng);
+        for i in 1..=sentences {
+            words.push_sentence(&mut builder);
+            if every > 0 && i % every == 0 {
+                for word in refrain.split_whitespace() {
+                    builder.push_word(word);
+                }
+                builder.end_sentence();
+            }
+        }
+        builder.finish()
+    }
+
+    /// Generate `n` words by alternating between this chain and
+    /// `other`.
+    ///
+    /// The two chains take turns choosing the next word from the
+    /// current state. When the chain whose turn it is does not know
+    /// the current state, it jumps to a random state of its own. The
+    /// result blends the vocabularies of both chains. If one chain is
+    /// empty, only the other chain is used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut latin = MarkovChain::new();
+    /// latin.learn("lorem ipsum dolor sit amet consectetur adipiscing elit");
+    /// let mut english = MarkovChain::new();
+    /// english.learn("the quick brown fox jumps over the lazy dog");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// println!("{}", latin.generate_interleaved(&english, rng, 10));
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn generate_interleaved<R: Rng>(
+        &self,
+        other: &MarkovChain<'a>,
+        mut rng: R,
+        n: usize,
+    ) -> String {
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        let chains = [self, other];
+        let mut state = match self.keys.choose(&mut rng) {
+            Some(&state) => state,
+            None => match other.keys.choose(&mut rng) {
+                Some(&state) => state,
+                None => return builder.finish(),
+            },
+        };
+
+        for i in 0..n {
+            builder.push_word(state.0);
+            let mut chain = chains[i % 2];
+            if chain.is_empty() {
+                chain = chains[(i + 1) % 2];
+            }
+            if !chain.map.contains_key(&state) {
+                state = *chain.keys.choose(&mut rng).unwrap();
+            }
+            let next = chain.map[&state].choose(&mut rng).unwrap();
+            state = (state.1, next);
+        }
+        builder.finish()
+    }
+
+    /// Generate an abecedarian text: sentences whose first words
+    /// start with the letters A, B, C, and so on, in order.
+    ///
+    /// For each letter from A to Z, a random bigram from
+    /// [`sentence_starts`] whose first word begins with the letter
+    /// is picked and a sentence is generated from it. Letters without
+    /// such a bigram are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("Dates are brown. Apples are red. Bananas are yellow. Dates");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// assert_eq!(
+    ///     chain.generate_abecedarian(rng),
+    ///     "Apples are red. Bananas are yellow. Dates are brown."
+    /// );
+    /// ```
+    ///
+    /// [`sentence_starts`]: struct.MarkovChain.html#method.sentence_starts
+    #[cfg(feature = "rand")]
+    pub fn generate_abecedarian<R: Rng>(&self, mut rng: R) -> String {
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        let starts = self.sentence_starts();
+        for letter in 'a'..='z' {
+            let candidates = starts
+                .iter()
+                .filter(|(word, _)| {
+                    word.chars()
+                        .next()
+                        .map_or(false, |c| c.to_ascii_lowercase() == letter)
+                })
+                .collect::<Vec<_>>();
+            if let Some(&&start) = candidates.choose(&mut rng) {
+                let mut words = self.iter_with_rng_from(&mut rng, start);
+                words.push_sentence(&mut builder);
+            }
+        }
+        builder.finish()
+    }
+
+    /// Generate `n` words of lorem ipsum text, but only if the chain
+    /// is diverse enough to produce new text.
+    ///
+    /// This works like [`generate_with_rng`], except that an error is
+    /// returned if the [`diversity`] of the chain is below 1%. Such a
+    /// chain mostly repeats the learned text verbatim.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LowDiversityError`] with the measured diversity if
+    /// the chain is too repetitive. This includes the empty chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("one two three four five");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// let error = chain.generate_checked(rng, 10).unwrap_err();
+    /// assert_eq!(error.diversity, 0.0);
+    ///
+    /// chain.learn("one two four three five");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// assert!(chain.generate_checked(rng, 10).is_ok());
+    /// ```
+    ///
+    /// [`generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
+    /// [`diversity`]: struct.MarkovChain.html#method.diversity
+    /// [`LowDiversityError`]: struct.LowDiversityError.html
+    #[cfg(feature = "rand")]
+    pub fn generate_checked<R: Rng>(&self, rng: R, n: usize) -> Result<String, LowDiversityError> {
+        let diversity = self.diversity();
+        if diversity < MIN_DIVERSITY {
+            return Err(LowDiversityError { diversity });
+        }
+        Ok(self.generate_with_rng(rng, n))
+    }
+
+    /// Generate `count` samples of `words_each` words which do not
+    /// overlap much.
+    ///
+    /// The similarity of two samples is the fraction of word trigrams
+    /// they share, ignoring case and punctuation, out of the trigrams
+    /// in the shorter sample. A sample whose similarity to a previous
+    /// sample is above 25% is generated again, up to 10 times. This
+    /// is best-effort: if no attempt is below the threshold, the
+    /// least similar attempt is used. This happens with small chains
+    /// which can only produce a few different texts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("The quick brown fox jumps over the lazy dog and the quick cat.");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// let samples = chain.generate_distinct(rng, 3, 5);
+    /// assert_eq!(samples.len(), 3);
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn generate_distinct<R: Rng>(
+        &self,
+        mut rng: R,
+        count: usize,
+        words_each: usize,
+    ) -> Vec<String> {
+        let mut samples: Vec<String> = Vec::with_capacity(count);
+        let mut trigrams: Vec<HashSet<String>> = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut best = None;
+            for _ in 0..DISTINCT_ATTEMPTS {
+                let sample = self.generate_with_rng(&mut rng, words_each);
+                let sample_trigrams = word_trigrams(&sample);
+                let similarity = trigrams
+                    .iter()
+                    .map(|previous| similarity(&sample_trigrams, previous))
+                    .fold(0.0, f64::max);
+                let better = match best {
+                    Some((best_similarity, _, _)) => similarity < best_similarity,
+                    None => true,
+                };
+                if better {
+                    best = Some((similarity, sample, sample_trigrams));
+                }
+                if similarity <= MAX_DISTINCT_SIMILARITY {
+                    break;
+                }
+            }
+            if let Some((_, sample, sample_trigrams)) = best {
+                samples.push(sample);
+                trigrams.push(sample_trigrams);
+            }
+        }
+        samples
+    }
+
+    /// Generate up to `n` words of lorem ipsum text, but stop after
+    /// `max_steps` steps.
+    ///
+    /// This guards against accidentally huge values of `n`, e.g.,
+    /// when `n` comes from untrusted input. Note that `max_steps` is
+    /// the bound on the generation loop, not a word count: each step
+    /// moves the Markov chain to its next state and produces one
+    /// word. The generation stops when either bound is reached and
+    /// the text generated so far is finished with a `.` as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red orange yellow green blue indigo violet");
+    /// let rng = ChaCha20Rng::seed_from_u64(1);
+    /// assert_eq!(chain.generate_bounded(rng, usize::MAX, 3), "Green blue indigo.");
+    /// ```
+    #[cfg(feature = "rand")]
+    pub fn generate_bounded<R: Rng>(&self, rng: R, n: usize, max_steps: usize) -> String {
+        let mut words = self.iter_with_rng(rng);
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        for _ in 0..n.min(max_steps) {
+            match words.next() {
+                Some(word) => builder.push_word(word),
+                None => break,
+            }
+        }
+        builder.finish()
+    }
+
+    /// Generate `n` lowercase words without any punctuation.
+    ///
+    /// All ASCII punctuation characters are removed from the words
+    /// and the words are joined with a single space. Unlike
+    /// [`generate_with_rng`], no words are capitalized and no
+    /// terminator is added. Words consisting entirely of punctuation
+    /// are skipped, so the output normally has `n` words. Fewer words
+    /// are returned if the chain is empty or keeps producing only
+    /// punctuation, e.g., because it has learned nothing else.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("Red, orange, yellow! Green, blue, and indigo.");
+    /// let rng = ChaCha20Rng::seed_from_u64(3);
+    /// assert_eq!(chain.generate_plain(rng, 4), "orange yellow green blue");
+    /// ```
+    ///
+    /// [`generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
+    #[cfg(feature = "rand")]
+    pub fn generate_plain<R: Rng>(&self, rng: R, n: usize) -> String {
+        let mut words = self.iter_with_rng(rng);
+        let mut text = String::new();
+        let mut count = 0;
+        // Stop if the chain produces nothing but punctuation.
+        let mut idle = 0;
+        while count < n && idle <= self.len() {
+            let word = match words.next() {
+                Some(word) => word.replace(is_ascii_punctuation, "").to_lowercase(),
+                None => break,
+            };
+            if word.is_empty() {
+                idle += 1;
+                continue;
+            }
+            idle = 0;
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            text.push_str(&word);
+            count += 1;
+        }
+        text
+    }
+
+    /// Generate `n` characters from a character-level Markov chain.
+    ///
+    /// The tokens produced by the chain are concatenated without any
+    /// separator, so this is intended for chains built with
+    /// [`learn_char_level`]. No capitalization or punctuation is
+    /// added.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn_char_level("lorem ipsum dolor sit amet");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// assert_eq!(chain.generate_chars(rng, 20).chars().count(), 20);
+    /// ```
+    ///
+    /// [`learn_char_level`]: struct.MarkovChain.html#method.learn_char_level
+    #[cfg(feature = "rand")]
+    pub fn generate_chars<R: Rng>(&self, rng: R, n: usize) -> String {
+        self.iter_with_rng(rng).take(n).collect()
+    }
+
+    /// Generate `paragraphs` paragraphs with `n` words each.
+    ///
+    /// Each paragraph is generated with a [`ChaCha20Rng`] seeded with
+    /// `master_seed.wrapping_add(i)`, where `i` is the index of the
+    /// paragraph, starting from zero. The paragraphs will thus differ
+    /// from each other, while the whole document can be reproduced
+    /// from the single `master_seed`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red orange yellow green blue indigo violet red green blue");
+    /// let paragraphs = chain.generate_paragraphs_seeded(42, 3, 10);
+    /// assert_eq!(paragraphs.len(), 3);
+    /// assert_eq!(paragraphs, chain.generate_paragraphs_seeded(42, 3, 10));
+    /// println!("{}", paragraphs.join("\n\n"));
+    /// ```
+    ///
+    /// [`ChaCha20Rng`]: https://docs.rs/rand_chacha/latest/rand_chacha/struct.ChaCha20Rng.html
+    #[cfg(feature = "rand")]
+    pub fn generate_paragraphs_seeded(
+        &self,
+        master_seed: u64,
+        paragraphs: usize,
+        n: usize,
+    ) -> Vec<String> {
+        (0..paragraphs)
+            .map(|i| {
+                let rng = ChaCha20Rng::seed_from_u64(master_seed.wrapping_add(i as u64));
+                self.generate_with_rng(rng, n)
+            })
+            .collect()
+    }
+
+    /// Generate `n` words of lorem ipsum text with sentences of
+    /// roughly `target` words.
+    ///
+    /// Sentence lengths follow a [logistic distribution] centered at
+    /// `target` with a scale of a fifth of `target`. In practice, this
+    /// means that after each word, the sentence is ended with a
+    /// probability which increases along a logistic curve as the
+    /// sentence approaches `target` words. Most sentences will thus
+    /// land near the target, while some will be shorter or longer.
+    ///
+    /// Sentences can still be shorter than expected when the chain
+    /// produces a word which ends with `.`, `!`, or `?`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("a b c d e f g h a b d e c f g a h");
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// let text = chain.generate_targeted_length(rng, 30, 3);
+    /// assert_eq!(text.split_whitespace().count(), 30);
+    /// println!("{}", text);
+    /// ```
+    ///
+    /// [logistic distribution]: https://en.wikipedia.org/wiki/Logistic_distribution
+    #[cfg(feature = "rand")]
+    pub fn generate_targeted_length<R: Rng>(&self, rng: R, n: usize, target: usize) -> String {
+        self.generate_targeted_length_with_options(rng, n, target, &Options::new())
+    }
+
+    /// Generate `n` words of lorem ipsum text aimed at a
+    /// readability level.
+    ///
+    /// This is a heuristic which combines two existing knobs: the
+    /// [`WordLengthBias`] used when selecting words and the sentence
+    /// length targeted by [`generate_targeted_length`]. Easier levels
+    /// prefer shorter words and sentences, harder levels prefer
+    /// longer words and sentences. This shifts scores such as the
+    /// [Flesch reading ease] in the expected direction, but no
+    /// particular score is guaranteed. See [`Readability`] for the
+    /// settings used for each level.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Readability, LIBER_PRIMUS};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn(LIBER_PRIMUS);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// println!("{}", chain.generate_readability(rng, 30, Readability::Easy));
+    /// ```
+    ///
+    /// [`WordLengthBias`]: enum.WordLengthBias.html
+    /// [`generate_targeted_length`]: struct.MarkovChain.html#method.generate_targeted_length
+    /// [Flesch reading ease]: https://en.wikipedia.org/wiki/Flesch%E2%80%93Kincaid_readability_tests
+    /// [`Readability`]: enum.Readability.html
+    #[cfg(feature = "rand")]
+    pub fn generate_readability<R: Rng>(&self, rng: R, n: usize, target: Readability) -> String {
+        let options = Options::new().word_length_bias(target.word_length_bias());
+        self.generate_targeted_length_with_options(rng, n, target.sentence_words(), &options)
+    }
+
+    /// Generate `n` words with sentences of roughly `target` words,
+    /// selecting words according to `options`.
+    #[cfg(feature = "rand")]
+    fn generate_targeted_length_with_options<R: Rng>(
+        &self,
+        rng: R,
+        n: usize,
+        target: usize,
+        options: &Options,
+    ) -> String {
+        let target = target as f64;
+        let scale = (target / 5.0).max(1.0);
+        // Cumulative distribution function for the sentence length.
+        let cdf = |len: f64| 1.0 / (1.0 + (-(len - target) / scale).exp());
+
+        let mut words = self.iter_with_options(rng, options);
+        let mut builder = TextBuilder::new(options);
+        for i in 0..n {
+            let word = match words.next() {
+                Some(word) => word,
+                None => break,
+            };
+            builder.push_word(word);
+
+            if builder.sentence_words > 0 && i + 1 < n {
+                // Probability of ending the sentence here, given that
+                // it has not ended before.
+                let len = builder.sentence_words as f64;
+                let survival = 1.0 - cdf(len - 0.5);
+                let p = if survival > 0.0 {
+                    ((cdf(len + 0.5) - cdf(len - 0.5)) / survival).min(1.0)
+                } else {
+                    1.0
+                };
+                if words.rng.gen_bool(p) {
+                    builder.end_sentence();
+                }
+            }
+        }
+        builder.finish()
+    }
+
+    /// Make a never-ending iterator over the words in the Markov
+    /// chain. The iterator starts at a random point in the chain.
+    ///
+    /// Any random number generator implementing [`Rng`] can be used.
+    /// A fast non-cryptographic generator such as [`SmallRng`] works
+    /// well, but note that it requires enabling the `small_rng`
+    /// feature of the `rand` crate.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::rngs::SmallRng;
+    /// use rand::SeedableRng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("one two three four");
+    /// let rng = SmallRng::seed_from_u64(0);
+    /// assert_eq!(chain.iter_with_rng(rng).take(4).count(), 4);
+    /// ```
+    ///
+    /// [`Rng`]: https://docs.rs/rand/latest/rand/trait.Rng.html
+    /// [`SmallRng`]: https://docs.rs/rand/latest/rand/rngs/struct.SmallRng.html
+    #[cfg(feature = "rand")]
+    pub fn iter_with_rng<R: Rng>(&self, mut rng: R) -> Words<'_, R> {
+        let initial_bigram = if self.is_empty() {
+            ("", "")
+        } else if !self.starts.is_empty() {
+            *self.starts.choose(&mut rng).unwrap()
+        } else {
+            *self.keys.choose(&mut rng).unwrap()
+        };
+        self.iter_with_rng_from(rng, initial_bigram)
+    }
+
+    /// Make a never-ending iterator over the words in the Markov chain. The
+    /// iterator starts at a predetermined point in the chain.
+    #[cfg(feature = "rand")]
+    pub fn iter(&self) -> Words<'_, impl Rng> {
+        self.iter_with_rng(default_rng())
+    }
+
+    /// Make a never-ending iterator over the words in the Markov
+    /// chain. The iterator starts at the given bigram.
+    #[cfg(feature = "rand")]
+    pub fn iter_with_rng_from<R: Rng>(&self, rng: R, from: Bigram<'a>) -> Words<'_, R> {
+        self.words_from(rng, from)
+    }
+
+    /// Make the iterator behind [`iter_with_rng_from`] and
+    /// [`iter_from`]. It is available without the `rand` feature.
+    ///
+    /// [`iter_with_rng_from`]: struct.MarkovChain.html#method.iter_with_rng_from
+    /// [`iter_from`]: struct.MarkovChain.html#method.iter_from
+    fn words_from<R: Rng>(&self, rng: R, from: Bigram<'a>) -> Words<'_, R> {
+        Words {
+            map: &self.map,
+            rng,
+            keys: &self.keys,
+            starts: &self.starts,
+            state: from,
+            options: Options::new(),
+            short_run: 0,
+            recent: VecDeque::new(),
+            recent_states: VecDeque::new(),
+            blocked: HashSet::new(),
+            allowed_keys: None,
+            allowed_starts: None,
+            paragraph_break: false,
+        }
+    }
+
+    /// Make a never-ending iterator over the words in the Markov
+    /// chain, which selects words according to `options`. The
+    /// iterator starts at a random point in the chain.
+    #[cfg(feature = "rand")]
+    fn iter_with_options<'o, R: Rng>(&'o self, rng: R, options: &Options<'o>) -> Words<'o, R> {
+        let mut words = self.iter_with_rng(rng);
+        words.options = options.clone();
+        if let Some(blocklist) = options.blocklist {
+            words.blocked = blocklist
+                .iter()
+                .map(|word| word.trim_matches(is_ascii_punctuation).to_lowercase())
+                .collect();
+        }
+        if !self.is_empty() && words.is_blocked(words.state) {
+            words.state = words.random_key();
+        }
+        words
+    }
+
+    /// Make a never-ending iterator over the words in the Markov
+    /// chain. The iterator starts at the given bigram.
+    pub fn iter_from(&self, from: Bigram<'a>) -> Words<'_, impl Rng> {
+        self.words_from(default_rng(), from)
+    }
+
+    /// Make a never-ending iterator which always follows the most
+    /// frequent successor, starting at the given bigram.
+    ///
+    /// No random number generator is involved: ties are broken by
+    /// picking the first word in sort order and dead ends continue
+    /// from the first bigram in sort order. Iterating twice from the
+    /// same bigram thus gives the same words. This is the iterator
+    /// behind [`generate_greedy`]. The iterator is empty if the chain
+    /// is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("the cat sat. the cat ran. the cat sat.");
+    /// let words = chain.iter_deterministic(("the", "cat")).take(4).collect::<Vec<_>>();
+    /// assert_eq!(words, vec!["the", "cat", "sat.", "the"]);
+    /// ```
+    ///
+    /// [`generate_greedy`]: struct.MarkovChain.html#method.generate_greedy
+    pub fn iter_deterministic(&self, from: Bigram<'a>) -> impl Iterator<Item = &'a str> + '_ {
+        let mut state = from;
+        std::iter::from_fn(move || loop {
+            if self.map.is_empty() {
+                return None;
+            }
+            let word = state.0;
+            if !self.map.contains_key(&state) {
+                state = self.keys[0];
+            }
+            state = (state.1, most_frequent(&self.map[&state]));
+            // Paragraph breaks are not words.
+            if word != PARAGRAPH_BREAK {
+                return Some(word);
+            }
+        })
+    }
+
+    /// Resume iteration from a state saved with [`Words::save`].
+    ///
+    /// The returned iterator continues exactly where the saved
+    /// iterator stopped. This returns `None` if the saved words are
+    /// unknown to this Markov chain, which is typically because the
+    /// state was saved from a different chain.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red orange yellow green blue red yellow blue green");
+    ///
+    /// let mut words = chain.iter_with_rng(ChaCha20Rng::seed_from_u64(0));
+    /// let first = words.by_ref().take(5).collect::<Vec<_>>();
+    /// let saved = words.save();
+    /// let rest = words.take(5).collect::<Vec<_>>();
+    ///
+    /// let resumed = chain.resume(&saved).unwrap();
+    /// assert_eq!(resumed.take(5).collect::<Vec<_>>(), rest);
+    /// ```
+    ///
+    /// [`Words::save`]: struct.Words.html#method.save
+    #[cfg(feature = "rand")]
+    pub fn resume(&self, saved: &GeneratorState) -> Option<Words<'_, ChaCha20Rng>> {
+        let state = if self.is_empty() {
+            ("", "")
+        } else {
+            (
+                self.find_word(&saved.state.0)?,
+                self.find_word(&saved.state.1)?,
+            )
+        };
+        let mut rng = ChaCha20Rng::from_seed(saved.seed);
+        rng.set_stream(saved.stream);
+        rng.set_word_pos(saved.word_pos);
+        Some(self.iter_with_rng_from(rng, state))
+    }
+
+    /// Find `word` in the Markov chain.
+    #[cfg(feature = "rand")]
+    fn find_word(&self, word: &str) -> Option<&'a str> {
+        for (&(a, b), successors) in &self.map {
+            if a == word {
+                return Some(a);
+            }
+            if b == word {
+                return Some(b);
+            }
+            if let Some(&c) = successors.iter().find(|&&c| c == word) {
+                return Some(c);
+            }
+        }
+        None
+    }
+}
+
+impl PartialEq for MarkovChain<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.keys == other.keys
+            && self.keys.iter().all(|state| {
+                let mut ours = self.map[state].clone();
+                let mut theirs = other.map[state].clone();
+                ours.sort_unstable();
+                theirs.sort_unstable();
+                ours == theirs
+            })
+            && sorted_starts(self) == sorted_starts(other)
+    }
+}
+
+impl Eq for MarkovChain<'_> {}
+
+impl Hash for MarkovChain<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let mut successors = Vec::new();
+        self.keys.len().hash(state);
+        // The keys are sorted, so only the successors need sorting.
+        for key in &self.keys {
+            successors.clear();
+            successors.extend_from_slice(&self.map[key]);
+            successors.sort_unstable();
+            key.hash(state);
+            successors.hash(state);
+        }
+        sorted_starts(self).hash(state);
+    }
+}
+
+impl<'a> TryFrom<&'a str> for MarkovChain<'a> {
+    type Error = ChainError;
+
+    /// Create a Markov chain which has learned `text`.
+    ///
+    /// This fails with [`ChainError::Empty`] if the text is too short
+    /// to generate anything from, i.e., if it has fewer than three
+    /// words.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{ChainError, MarkovChain};
+    ///
+    /// assert!(MarkovChain::try_from("one two three").is_ok());
+    /// assert_eq!(MarkovChain::try_from("one two").unwrap_err(), ChainError::Empty);
+    /// ```
+    ///
+    /// [`ChainError::Empty`]: enum.ChainError.html#variant.Empty
+    fn try_from(text: &'a str) -> Result<Self, Self::Error> {
+        let mut chain = MarkovChain::new();
+        chain.learn(text);
+        if chain.is_empty() {
+            return Err(ChainError::Empty);
+        }
+        Ok(chain)
+    }
+}
+
+/// Errors which can occur when building a [`MarkovChain`].
+///
+/// [`MarkovChain`]: struct.MarkovChain.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ChainError {
+    /// The chain has no states. At least three words are needed to
+    /// form a transition from a bigram to the next word.
+    Empty,
+    /// The states are not sorted or contain duplicates.
+    UnsortedKeys,
+    /// The states do not match the states in the transition map.
+    InconsistentKeys,
+    /// A state has no successors.
+    EmptySuccessors,
+    /// A line could not be parsed as a transition.
+    Parse {
+        /// The line number, starting from 1.
+        line: usize,
+    },
+}
+
+impl std::fmt::Display for ChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChainError::Empty => write!(f, "the Markov chain has no states"),
+            ChainError::UnsortedKeys => write!(f, "the states are not sorted"),
+            ChainError::InconsistentKeys => {
+                write!(f, "the states do not match the transitions")
+            }
+            ChainError::EmptySuccessors => write!(f, "a state has no successors"),
+            ChainError::Parse { line } => write!(f, "invalid transition on line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for ChainError {}
+
+/// Error returned by [`MarkovChain::generate_checked`] when the chain
+/// is too repetitive.
+///
+/// [`MarkovChain::generate_checked`]: struct.MarkovChain.html#method.generate_checked
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LowDiversityError {
+    /// The measured [`MarkovChain::diversity`].
+    ///
+    /// [`MarkovChain::diversity`]: struct.MarkovChain.html#method.diversity
+    pub diversity: f64,
+}
+
+impl std::fmt::Display for LowDiversityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "the Markov chain is too repetitive (diversity {})",
+            self.diversity
+        )
+    }
+}
+
+impl std::error::Error for LowDiversityError {}
+
+/// Summary statistics of a Markov chain.
+///
+/// Returned by [`MarkovChain::stats`].
+///
+/// [`MarkovChain::stats`]: struct.MarkovChain.html#method.stats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChainStats {
+    /// Number of states, see [`MarkovChain::len`].
+    ///
+    /// [`MarkovChain::len`]: struct.MarkovChain.html#method.len
+    pub states: usize,
+    /// Total number of words learned.
+    pub tokens: usize,
+    /// Number of distinct words, see [`MarkovChain::distinct_words`].
+    ///
+    /// [`MarkovChain::distinct_words`]: struct.MarkovChain.html#method.distinct_words
+    pub distinct_words: usize,
+    /// Average number of characters in the distinct words.
+    pub avg_word_len: f64,
+    /// Average number of distinct successors of the states.
+    pub avg_successors: f64,
+    /// Number of dead ends, see [`MarkovChain::dead_ends`].
+    ///
+    /// [`MarkovChain::dead_ends`]: struct.MarkovChain.html#method.dead_ends
+    pub dead_ends: usize,
+}
+
+/// Sequence of Markov chains used one after another.
+///
+/// This generates a number of sentences from one chain, then a number
+/// of sentences from the next chain, and so on. The chains are not
+/// merged, so each part of the text only uses the transitions from
+/// its own chain. Each part starts at a random sentence start of its
+/// chain, see [`MarkovChain::sentence_starts`], or at a random state
+/// if the chain has none. The text is joined with the usual
+/// capitalization.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::{ChainSequence, MarkovChain};
+/// use rand::SeedableRng;
+/// use rand_chacha::ChaCha20Rng;
+///
+/// let mut formal = MarkovChain::new();
+/// formal.learn("Dear sir, we regret to inform you. We remain yours truly.");
+/// let mut casual = MarkovChain::new();
+/// casual.learn("Hey, what is up? Not much, just chilling.");
+///
+/// let sequence = ChainSequence::new().then(&formal, 1).then(&casual, 2);
+/// let rng = ChaCha20Rng::seed_from_u64(0);
+/// println!("{}", sequence.generate_with_rng(rng));
+/// ```
+///
+/// [`MarkovChain::sentence_starts`]: struct.MarkovChain.html#method.sentence_starts
+#[derive(Debug, Clone, Default)]
+pub struct ChainSequence<'a> {
+    parts: Vec<(&'a MarkovChain<'a>, usize)>,
+}
+
+impl<'a> ChainSequence<'a> {
+    /// Create a new empty sequence.
+    pub fn new() -> ChainSequence<'a> {
+        Default::default()
+    }
+
+    /// Add `sentences` sentences generated by `chain` to the end of
+    /// the sequence.
+    pub fn then(mut self, chain: &'a MarkovChain<'a>, sentences: usize) -> ChainSequence<'a> {
+        self.parts.push((chain, sentences));
+        self
+    }
+
+    /// Generate the sentences from all chains in the sequence using
+    /// the specified random number generator.
+    #[cfg(feature = "rand")]
+    pub fn generate_with_rng<R: Rng>(&self, mut rng: R) -> String {
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        for &(chain, sentences) in &self.parts {
+            if chain.is_empty() {
+                continue;
+            }
+            let starts = chain.sentence_starts();
+            let mut words = match starts.choose(&mut rng) {
+                Some(&from) => chain.iter_with_rng_from(&mut rng, from),
+                None => chain.iter_with_rng(&mut rng),
+            };
+            for _ in 0..sentences {
+                words.push_sentence(&mut builder);
+            }
+        }
+        builder.finish()
+    }
+}
+
+/// Cache of Markov chains built from corpora identified by a key.
+///
+/// Each chain is learned the first time its key is requested and is
+/// then shared between all callers. The cache can be used from
+/// several threads at once, which makes it useful for building
+/// chains on demand in request handlers.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::ChainCache;
+///
+/// let cache = ChainCache::new();
+/// let chain = cache.get_or_learn("colors", || "red orange yellow green blue");
+/// assert_eq!(chain.len(), 3);
+///
+/// // The corpus is not needed again.
+/// let again = cache.get_or_learn("colors", || unreachable!());
+/// assert_eq!(again.len(), 3);
+/// ```
+#[derive(Debug)]
+pub struct ChainCache<'a, K> {
+    chains: Mutex<HashMap<K, Arc<MarkovChain<'a>>>>,
+}
+
+impl<'a, K: Eq + Hash> ChainCache<'a, K> {
+    /// Create a new empty cache.
+    pub fn new() -> ChainCache<'a, K> {
+        ChainCache {
+            chains: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return the chain for `key`, learning it from the text returned
+    /// by `corpus` if the key is not yet in the cache.
+    ///
+    /// The `corpus` closure is called at most once per key. The cache
+    /// is locked while the chain is learned.
+    pub fn get_or_learn<F>(&self, key: K, corpus: F) -> Arc<MarkovChain<'a>>
+    where
+        F: FnOnce() -> &'a str,
+    {
+        let mut chains = self
+            .chains
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let chain = chains.entry(key).or_insert_with(|| {
+            let mut chain = MarkovChain::new();
+            chain.learn(corpus());
+            Arc::new(chain)
+        });
+        Arc::clone(chain)
+    }
+}
+
+impl<K: Eq + Hash> Default for ChainCache<'_, K> {
+    fn default() -> Self {
+        ChainCache::new()
+    }
+}
+
+/// Text owned for learning a Markov chain.
+///
+/// A [`MarkovChain`] borrows the words it learns, so it cannot
+/// outlive its text. `OwnedCorpus` keeps the text and learns chains
+/// borrowing from it with [`chain`]. This is useful when the text is
+/// read at runtime, e.g., with [`MarkovChain::from_file`], and no
+/// other value owns it.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::OwnedCorpus;
+///
+/// let corpus = OwnedCorpus::new(String::from("red orange yellow green"));
+/// let chain = corpus.chain();
+/// assert_eq!(chain.words(("red", "orange")), Some(&vec!["yellow"]));
+/// ```
+///
+/// [`MarkovChain`]: struct.MarkovChain.html
+/// [`chain`]: struct.OwnedCorpus.html#method.chain
+/// [`MarkovChain::from_file`]: struct.MarkovChain.html#method.from_file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnedCorpus {
+    text: String,
+}
+
+impl OwnedCorpus {
+    /// Take ownership of `text` for learning.
+    pub fn new(text: String) -> OwnedCorpus {
+        OwnedCorpus { text }
+    }
+
+    /// The owned text.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Learn the owned text and return the chain.
+    ///
+    /// The text is learned with [`MarkovChain::learn`] on every call,
+    /// so keep the returned chain around instead of calling this
+    /// repeatedly.
+    ///
+    /// [`MarkovChain::learn`]: struct.MarkovChain.html#method.learn
+    pub fn chain(&self) -> MarkovChain<'_> {
+        let mut chain = MarkovChain::new();
+        chain.learn(&self.text);
+        chain
+    }
+}
+
+impl From<String> for OwnedCorpus {
+    fn from(text: String) -> OwnedCorpus {
+        OwnedCorpus::new(text)
+    }
+}
+
+/// Differences between two Markov chains.
+///
+/// Returned by [`MarkovChain::diff`].
+///
+/// [`MarkovChain::diff`]: struct.MarkovChain.html#method.diff
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainDiff<'a> {
+    /// Bigrams which are only found in the first chain.
+    pub only_in_self: Vec<Bigram<'a>>,
+    /// Bigrams which are only found in the second chain.
+    pub only_in_other: Vec<Bigram<'a>>,
+    /// Bigrams found in both chains, but with different successors.
+    pub changed: Vec<Bigram<'a>>,
+}
+
+/// Saved position of a [`Words`] iterator.
+///
+/// This captures the current bigram and the full state of the
+/// [`ChaCha20Rng`] used by the iterator. All fields are public, which
+/// allows you to persist the state in whatever format you like, for
+/// example between requests on a server. Use [`MarkovChain::resume`]
+/// to continue generating words from the saved state.
+///
+/// [`Words`]: struct.Words.html
+/// [`ChaCha20Rng`]: https://docs.rs/rand_chacha/latest/rand_chacha/struct.ChaCha20Rng.html
+/// [`MarkovChain::resume`]: struct.MarkovChain.html#method.resume
+#[cfg(feature = "rand")]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GeneratorState {
+    /// The current bigram. The first word is the next word which will
+    /// be produced by the iterator.
+    pub state: (String, String),
+    /// Seed of the random number generator.
+    pub seed: [u8; 32],
+    /// Stream of the random number generator.
+    pub stream: u64,
+    /// Position in the stream of the random number generator.
+    pub word_pos: u128,
+}
+
+/// Options for generating text.
+///
+/// The options control how words are selected from the Markov chain
+/// and how they are joined into sentences. They are used with
+/// [`MarkovChain::generate_with_options`]. Create them with
+/// [`Options::new`] and use the builder methods to change the
+/// settings:
+///
+/// ```
+/// use lipsum::Options;
+///
+/// let options = Options::new().max_sentence_chars(160);
+/// assert_eq!(options.max_sentence_chars, Some(160));
+/// ```
+///
+/// [`MarkovChain::generate_with_options`]: struct.MarkovChain.html#method.generate_with_options
+/// [`Options::new`]: struct.Options.html#method.new
+#[derive(Clone)]
+pub struct Options<'a> {
+    /// Maximum number of characters in a sentence, including the
+    /// final punctuation.
+    ///
+    /// When adding the next word would make a sentence longer than
+    /// this, the sentence is ended with a `'.'` and the word starts a
+    /// new sentence. This only adds sentence breaks, the total number
+    /// of words generated is unaffected. A single word longer than
+    /// the limit will still be put into a sentence of its own.
+    pub max_sentence_chars: Option<usize>,
+    /// Predicate which determines if a word ends a sentence.
+    ///
+    /// The word following a terminating word is capitalized and no
+    /// `'.'` is added at the end of the text if the last word is a
+    /// terminating word. The default predicate checks if the word
+    /// ends with `.`, `!`, or `?`.
+    pub is_terminator: &'a dyn Fn(&str) -> bool,
+    /// Bias for selecting words based on their length.
+    pub word_length_bias: WordLengthBias,
+    /// Probability of inserting a comma between two words.
+    ///
+    /// This is independent of the commas in the learned text. No
+    /// comma is inserted after a word which already ends with
+    /// punctuation and no comma is left at the end of a sentence.
+    /// The default is `0.0`, which inserts no commas. Values above
+    /// `1.0` are treated as `1.0`.
+    pub comma_rate: f64,
+    /// Probability of joining two words with a hyphen.
+    ///
+    /// This creates compound words like "dolor-sit" by using a `'-'`
+    /// instead of a space between two words in a sentence. Words are
+    /// not joined if the first word ends with punctuation, if the
+    /// second word starts with punctuation, or if the first word
+    /// already contains a hyphen. The default is `0.0`, which joins
+    /// no words. Values above `1.0` are treated as `1.0`.
+    pub compound_rate: f64,
+    /// Probability of separating two words with an em-dash.
+    ///
+    /// This replaces the space between two words in a sentence with
+    /// `" — "`. No em-dash is inserted next to punctuation or before
+    /// a word which ends the sentence. The default is `0.0`, which
+    /// inserts no em-dashes. Values above `1.0` are treated as `1.0`.
+    pub em_dash_rate: f64,
+    /// End the text with a sentence terminator.
+    ///
+    /// When `true`, a `'.'` is added at the end of the text unless it
+    /// already ends a sentence. Set this to `false` to leave the last
+    /// word unchanged, e.g., when generating fragments which are
+    /// concatenated later. Punctuation within the text is unaffected.
+    /// The default is `true`.
+    pub terminate: bool,
+    /// Typographic style used for punctuation.
+    pub punctuation_style: PunctuationStyle,
+    /// Separate paragraphs with a blank line.
+    ///
+    /// When the chain has learned text with
+    /// [`MarkovChain::learn_paragraphs`], the paragraph boundaries
+    /// are generated like words. With this option, the current
+    /// sentence is ended at such a boundary and a blank line is
+    /// inserted. Otherwise, the boundaries are ignored. Paragraph
+    /// boundaries do not count as words. The default is `false`.
+    ///
+    /// [`MarkovChain::learn_paragraphs`]: struct.MarkovChain.html#method.learn_paragraphs
+    pub paragraph_breaks: bool,
+    /// Words which should not be generated.
+    ///
+    /// Successors in the blocklist are skipped when selecting the
+    /// next word. Words are compared without ASCII punctuation and
+    /// case, so `"bad"` also blocks `"Bad"` and `"bad,"`. This is a
+    /// soft filter: if all successors of a bigram are blocked, one of
+    /// them is selected anyway. Random jumps and restarts at sentence
+    /// starts likewise avoid states with blocked words when possible.
+    pub blocklist: Option<&'a HashSet<&'a str>>,
+    /// Maximum number of consecutive short words.
+    ///
+    /// Words with at most three characters, not counting punctuation,
+    /// are short. When this many short words have been generated in a
+    /// row, a longer successor is selected if the chain has one. This
+    /// avoids choppy runs such as "et ut in ad". The default is
+    /// `None`, which allows any number of short words in a row.
+    pub max_consecutive_short: Option<usize>,
+    /// Number of recent words which should not be repeated.
+    ///
+    /// A successor which is among the last `no_repeat_window` words
+    /// of the text is skipped when the chain has another successor.
+    /// This reduces local repetition beyond adjacent words. The
+    /// default is `0`, which allows any repetition.
+    pub no_repeat_window: usize,
+    /// How numbers in the learned text are generated.
+    ///
+    /// See [`NumberPolicy`] for the possible policies. The default
+    /// keeps the numbers unchanged.
+    ///
+    /// [`NumberPolicy`]: enum.NumberPolicy.html
+    pub numbers: NumberPolicy,
+    /// Maximum number of characters in a word.
+    ///
+    /// Longer words are cut at this length instead of being skipped,
+    /// so the generation follows the chain as usual. Trailing
+    /// punctuation is kept and not counted, and no ellipsis is added.
+    /// At least one character is kept. The default is `None`, which
+    /// leaves words unchanged.
+    pub truncate_long_words: Option<usize>,
+    /// Temperature used when selecting the next word.
+    ///
+    /// Each distinct successor is weighted by its count raised to
+    /// the power of `1.0 / temperature`. A temperature of `1.0`
+    /// selects words proportionally to their frequency, which is the
+    /// default. Lower temperatures favor frequent words and a
+    /// temperature of `0.0` or less always selects the most frequent
+    /// word. Higher temperatures flatten the distribution toward
+    /// selecting all successors uniformly. A temperature which is
+    /// `NaN` is treated as `1.0`.
+    pub temperature: f64,
+    /// Weights for choosing the terminator added to end a sentence.
+    ///
+    /// The weights are for `'.'`, `'!'`, and `'?'`, in that order.
+    /// A terminator is chosen with these weights for each sentence
+    /// and used if the sentence is ended without a terminator from
+    /// the learned text, e.g., because of [`max_sentence_chars`] or
+    /// at the end of the text. Terminators in the learned text are
+    /// unchanged. The default is `None`, which always adds a `'.'`.
+    /// If all weights are zero, a `'.'` is added.
+    ///
+    /// [`max_sentence_chars`]: #structfield.max_sentence_chars
+    pub terminator_weights: Option<[u32; 3]>,
+    /// Number of recent states which should not be revisited.
+    ///
+    /// A successor which would move the chain back to one of the last
+    /// `no_repeat_state_window` bigrams, including the current one,
+    /// is skipped when the chain has another successor. This breaks
+    /// short cycles such as "a b a b a b", which can happen with small
+    /// texts. Unlike [`no_repeat_window`], single words can still be
+    /// repeated. The default is `0`, which allows any state to be
+    /// revisited.
+    ///
+    /// [`no_repeat_window`]: #structfield.no_repeat_window
+    pub no_repeat_state_window: usize,
+}
+
+impl<'a> Options<'a> {
+    /// Create new options with default settings. This joins words
+    /// exactly like [`MarkovChain::generate_with_rng`].
+    ///
+    /// [`MarkovChain::generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
+    pub fn new() -> Options<'a> {
+        Options {
+            max_sentence_chars: None,
+            is_terminator: &ends_sentence,
+            word_length_bias: WordLengthBias::None,
+            comma_rate: 0.0,
+            compound_rate: 0.0,
+            em_dash_rate: 0.0,
+            terminate: true,
+            punctuation_style: PunctuationStyle::English,
+            paragraph_breaks: false,
+            blocklist: None,
+            max_consecutive_short: None,
+            no_repeat_window: 0,
+            numbers: NumberPolicy::Keep,
+            truncate_long_words: None,
+            temperature: 1.0,
+            terminator_weights: None,
+            no_repeat_state_window: 0,
+        }
+    }
+
+    /// Change [`self.max_sentence_chars`].
+    ///
+    /// [`self.max_sentence_chars`]: #structfield.max_sentence_chars
+    pub fn max_sentence_chars(self, max_chars: usize) -> Options<'a> {
+        Options {
+            max_sentence_chars: Some(max_chars),
+            ..self
+        }
+    }
+
+    /// Change [`self.is_terminator`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red orange… yellow green… blue indigo violet…");
+    /// let is_terminator = |word: &str| word.ends_with('…');
+    /// let options = Options::new().is_terminator(&is_terminator);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// assert_eq!(
+    ///     chain.generate_with_options(rng, 5, &options),
+    ///     "Orange… Yellow green… Blue indigo."
+    /// );
+    /// ```
+    ///
+    /// [`self.is_terminator`]: #structfield.is_terminator
+    pub fn is_terminator(self, is_terminator: &'a dyn Fn(&str) -> bool) -> Options<'a> {
+        Options {
+            is_terminator,
+            ..self
+        }
+    }
+
+    /// Change [`self.word_length_bias`].
+    ///
+    /// [`self.word_length_bias`]: #structfield.word_length_bias
+    pub fn word_length_bias(self, bias: WordLengthBias) -> Options<'a> {
+        Options {
+            word_length_bias: bias,
+            ..self
+        }
+    }
+
+    /// Change [`self.comma_rate`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("a b c d e f g h");
+    /// let options = Options::new().comma_rate(1.0);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// assert_eq!(
+    ///     chain.generate_with_options(rng, 4, &options),
+    ///     "D, e, f, g."
+    /// );
+    /// ```
+    ///
+    /// [`self.comma_rate`]: #structfield.comma_rate
+    pub fn comma_rate(self, rate: f64) -> Options<'a> {
+        Options {
+            comma_rate: rate,
+            ..self
+        }
+    }
+
+    /// Change [`self.compound_rate`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("a b c d e f g h");
+    /// let options = Options::new().compound_rate(1.0);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// assert_eq!(
+    ///     chain.generate_with_options(rng, 4, &options),
+    ///     "D-e f-g."
+    /// );
+    /// ```
+    ///
+    /// [`self.compound_rate`]: #structfield.compound_rate
+    pub fn compound_rate(self, rate: f64) -> Options<'a> {
+        Options {
+            compound_rate: rate,
+            ..self
+        }
+    }
+
+    /// Change [`self.em_dash_rate`].
+    ///
+    /// [`self.em_dash_rate`]: #structfield.em_dash_rate
+    pub fn em_dash_rate(self, rate: f64) -> Options<'a> {
+        Options {
+            em_dash_rate: rate,
+            ..self
+        }
+    }
+
+    /// Change [`self.terminate`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("a b c d e f g h");
+    /// let options = Options::new().terminate(false);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// assert_eq!(chain.generate_with_options(rng, 4, &options), "D e f g");
+    /// ```
+    ///
+    /// [`self.terminate`]: #structfield.terminate
+    pub fn terminate(self, terminate: bool) -> Options<'a> {
+        Options { terminate, ..self }
+    }
+
+    /// Change [`self.punctuation_style`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options, PunctuationStyle};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("Quoi? Vraiment! Oui, vraiment!");
+    /// let options = Options::new().punctuation_style(PunctuationStyle::French);
+    /// let rng = ChaCha20Rng::seed_from_u64(2);
+    /// assert_eq!(
+    ///     chain.generate_with_options(rng, 3, &options),
+    ///     "Quoi\u{202f}? Vraiment\u{202f}! Oui."
+    /// );
+    /// ```
+    ///
+    /// [`self.punctuation_style`]: #structfield.punctuation_style
+    pub fn punctuation_style(self, style: PunctuationStyle) -> Options<'a> {
+        Options {
+            punctuation_style: style,
+            ..self
+        }
+    }
+
+    /// Change [`self.paragraph_breaks`].
+    ///
+    /// [`self.paragraph_breaks`]: #structfield.paragraph_breaks
+    pub fn paragraph_breaks(self, paragraph_breaks: bool) -> Options<'a> {
+        Options {
+            paragraph_breaks,
+            ..self
+        }
+    }
+
+    /// Change [`self.blocklist`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    /// use std::collections::HashSet;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("I like red apples. I like green apples. I like bad apples.");
+    /// let blocklist = HashSet::from(["bad"]);
+    /// let options = Options::new().blocklist(&blocklist);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// let text = chain.generate_with_options(rng, 100, &options);
+    /// assert!(!text.contains("bad"));
+    /// ```
+    ///
+    /// [`self.blocklist`]: #structfield.blocklist
+    pub fn blocklist(self, blocklist: &'a HashSet<&'a str>) -> Options<'a> {
+        Options {
+            blocklist: Some(blocklist),
+            ..self
+        }
+    }
+
+    /// Change [`self.max_consecutive_short`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("et ut in ad et ut magnificus et ut in splendidus et ut in ad");
+    /// let options = Options::new().max_consecutive_short(2);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// let text = chain.generate_with_options(rng, 20, &options);
+    /// assert!(!text.to_lowercase().contains("et ut in"));
+    /// ```
+    ///
+    /// [`self.max_consecutive_short`]: #structfield.max_consecutive_short
+    pub fn max_consecutive_short(self, max_short: usize) -> Options<'a> {
+        Options {
+            max_consecutive_short: Some(max_short),
+            ..self
+        }
+    }
+
+    /// Change [`self.no_repeat_window`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("a b a b a c d a b e a b a f");
+    /// let options = Options::new().no_repeat_window(3);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// println!("{}", chain.generate_with_options(rng, 10, &options));
+    /// ```
+    ///
+    /// [`self.no_repeat_window`]: #structfield.no_repeat_window
+    pub fn no_repeat_window(self, window: usize) -> Options<'a> {
+        Options {
+            no_repeat_window: window,
+            ..self
+        }
+    }
+
+    /// Change [`self.numbers`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, NumberPolicy, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("in 1984 and 2001 we sang 3 songs in 1984");
+    /// let options = Options::new().numbers(NumberPolicy::Strip);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// let text = chain.generate_with_options(rng, 5, &options);
+    /// assert!(!text.contains(|c: char| c.is_ascii_digit()));
+    /// ```
+    ///
+    /// [`self.numbers`]: #structfield.numbers
+    pub fn numbers(self, numbers: NumberPolicy) -> Options<'a> {
+        Options { numbers, ..self }
+    }
+
+    /// Change [`self.truncate_long_words`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("Supercalifragilistic expialidocious, even though the sound of it is quite atrocious.");
+    /// let options = Options::new().truncate_long_words(5);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// assert_eq!(
+    ///     chain.generate_with_options(rng, 4, &options),
+    ///     "Super expia, even thoug."
+    /// );
+    /// ```
+    ///
+    /// [`self.truncate_long_words`]: #structfield.truncate_long_words
+    pub fn truncate_long_words(self, max_chars: usize) -> Options<'a> {
+        Options {
+            truncate_long_words: Some(max_chars),
+            ..self
+        }
+    }
+
+    /// Change [`self.temperature`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("a b c a b c a b c a b d a b");
+    /// let options = Options::new().temperature(0.0);
+    /// let rng = ChaCha20Rng::seed_from_u64(1);
+    /// let text = chain.generate_with_options(rng, 20, &options);
+    /// assert!(text.ends_with("a b c a b c a b c."));
+    /// ```
+    ///
+    /// [`self.temperature`]: #structfield.temperature
+    pub fn temperature(self, temperature: f64) -> Options<'a> {
+        Options {
+            temperature,
+            ..self
+        }
+    }
+
+    /// Change [`self.terminator_weights`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("red orange yellow green blue indigo violet");
+    /// let options = Options::new().terminator_weights([0, 1, 0]);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// assert!(chain.generate_with_options(rng, 5, &options).ends_with('!'));
+    /// ```
+    ///
+    /// [`self.terminator_weights`]: #structfield.terminator_weights
+    pub fn terminator_weights(self, weights: [u32; 3]) -> Options<'a> {
+        Options {
+            terminator_weights: Some(weights),
+            ..self
+        }
+    }
+
+    /// Change [`self.no_repeat_state_window`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::{MarkovChain, Options};
+    /// use rand::SeedableRng;
+    /// use rand_chacha::ChaCha20Rng;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("a b a b a b a c d a b a b a b");
+    /// let options = Options::new().no_repeat_state_window(2);
+    /// let rng = ChaCha20Rng::seed_from_u64(0);
+    /// let text = chain.generate_with_options(rng, 20, &options);
+    /// assert!(!text.to_lowercase().contains("a b a b"));
+    /// ```
+    ///
+    /// [`self.no_repeat_state_window`]: #structfield.no_repeat_state_window
+    pub fn no_repeat_state_window(self, window: usize) -> Options<'a> {
+        Options {
+            no_repeat_state_window: window,
+            ..self
+        }
+    }
+}
+
+impl Default for Options<'_> {
+    fn default() -> Self {
+        Options::new()
+    }
+}
+
+impl std::fmt::Debug for Options<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Options")
+            .field("max_sentence_chars", &self.max_sentence_chars)
+            .field("is_terminator", &"Fn(&str) -> bool")
+            .field("word_length_bias", &self.word_length_bias)
+            .field("comma_rate", &self.comma_rate)
+            .field("compound_rate", &self.compound_rate)
+            .field("em_dash_rate", &self.em_dash_rate)
+            .field("terminate", &self.terminate)
+            .field("punctuation_style", &self.punctuation_style)
+            .field("paragraph_breaks", &self.paragraph_breaks)
+            .field("blocklist", &self.blocklist)
+            .field("max_consecutive_short", &self.max_consecutive_short)
+            .field("no_repeat_window", &self.no_repeat_window)
+            .field("numbers", &self.numbers)
+            .field("truncate_long_words", &self.truncate_long_words)
+            .field("temperature", &self.temperature)
+            .field("terminator_weights", &self.terminator_weights)
+            .field("no_repeat_state_window", &self.no_repeat_state_window)
+            .finish()
+    }
+}
+
+/// Typographic style for punctuation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PunctuationStyle {
+    /// Punctuation follows the words directly, as in English. This is
+    /// the default.
+    English,
+    /// A narrow no-break space (U+202F) is inserted before `!`, `?`,
+    /// `;`, and `:`, as in French.
+    French,
+}
+
+impl Default for PunctuationStyle {
+    fn default() -> Self {
+        PunctuationStyle::English
+    }
+}
+
+/// Policy for numbers in the generated text.
+///
+/// A word is a number if it consists of ASCII digits and punctuation
+/// with at least one digit, such as `1984`, `3:16`, or `12,`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NumberPolicy {
+    /// Keep the numbers from the learned text. This is the default.
+    Keep,
+    /// Remove the numbers. A sentence which ended with a removed
+    /// number is still ended. Removed numbers do not count as words.
+    Strip,
+    /// Replace the numbers with random numbers from the range.
+    /// Punctuation around the number is kept. With an empty range,
+    /// the numbers are removed like with `Strip`.
+    Replace(Range<u64>),
+}
+
+impl Default for NumberPolicy {
+    fn default() -> Self {
+        NumberPolicy::Keep
+    }
+}
+
+/// Bias for selecting words based on their length.
+///
+/// The Markov chain normally selects the next word uniformly among
+/// the words which followed the current bigram in the learned text.
+/// With a bias, each candidate word is weighted by its length,
+/// measured in characters without surrounding punctuation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WordLengthBias {
+    /// No bias, all candidates are equally likely. This is the
+    /// default.
+    None,
+    /// Weight each candidate by its length. A word with 10 characters
+    /// is thus twice as likely as a word with 5 characters.
+    Longer,
+    /// Weight each candidate by the inverse of its length. A word
+    /// with 5 characters is thus twice as likely as a word with 10
+    /// characters.
+    Shorter,
+}
+
+impl WordLengthBias {
+    /// Weight of `word` when selecting among candidates.
+    fn weight(self, word: &str) -> f64 {
+        let len = word
+            .trim_matches(is_ascii_punctuation)
+            .chars()
+            .count()
+            .max(1) as f64;
+        match self {
+            WordLengthBias::None => 1.0,
+            WordLengthBias::Longer => len,
+            WordLengthBias::Shorter => 1.0 / len,
+        }
+    }
+}
+
+impl Default for WordLengthBias {
+    fn default() -> Self {
+        WordLengthBias::None
+    }
+}
+
+/// Readability level for [`MarkovChain::generate_readability`].
+///
+/// Each level selects a [`WordLengthBias`] and a target sentence
+/// length:
+///
+/// | Level      | Word length bias | Sentence length |
+/// |------------|------------------|-----------------|
+/// | `Easy`     | `Shorter`        | 8 words         |
+/// | `Standard` | `None`           | 15 words        |
+/// | `Hard`     | `Longer`         | 25 words        |
+///
+/// [`MarkovChain::generate_readability`]: struct.MarkovChain.html#method.generate_readability
+/// [`WordLengthBias`]: enum.WordLengthBias.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Readability {
+    /// Short words and short sentences.
+    Easy,
+    /// Unbiased words and medium sentences. This is the default.
+    Standard,
+    /// Long words and long sentences.
+    Hard,
+}
+
+#[cfg(feature = "rand")]
+impl Readability {
+    /// Bias for selecting words at this level.
+    fn word_length_bias(self) -> WordLengthBias {
+        match self {
+            Readability::Easy => WordLengthBias::Shorter,
+            Readability::Standard => WordLengthBias::None,
+            Readability::Hard => WordLengthBias::Longer,
+        }
+    }
+
+    /// Target number of words in a sentence at this level.
+    fn sentence_words(self) -> usize {
+        match self {
+            Readability::Easy => 8,
+            Readability::Standard => 15,
+            Readability::Hard => 25,
+        }
+    }
+}
+
+impl Default for Readability {
+    fn default() -> Self {
+        Readability::Standard
+    }
+}
+
+/// Check if `word` ends with `.`, `!`, or `?`.
+fn ends_sentence(word: &str) -> bool {
+    word.ends_with(TERMINATORS)
+}
+
+/// Provide a default random number generator. This generator is seeded and will
+/// always produce the same sequence of numbers. The seed is chosen to yield
+/// good results for the included Markov chain.
+#[cfg(feature = "rand")]
+fn default_rng() -> impl Rng {
+    ChaCha20Rng::seed_from_u64(97)
+}
+
+/// Provide the built-in xorshift generator when the `rand` feature is
+/// disabled. It uses the same seed, but yields a different sequence.
+#[cfg(not(feature = "rand"))]
+fn default_rng() -> impl Rng {
+    XorShift::new(97)
+}
+
+/// The parts of `rand` used by [`Words`] when the `rand` feature is
+/// disabled. The traits are public in a private module so that they
+/// can appear in the bounds of public types without being nameable
+/// outside this crate.
+///
+/// [`Words`]: struct.Words.html
+#[cfg(not(feature = "rand"))]
+mod xorshift {
+    /// Source of random numbers, like `rand::Rng`.
+    pub trait Rng {
+        /// Return the next 64 random bits.
+        fn next_u64(&mut self) -> u64;
+
+        /// Return a random number in `0..n`. The range must not be
+        /// empty.
+        fn below(&mut self, n: usize) -> usize {
+            (self.next_u64() % n as u64) as usize
+        }
+
+        /// Return a random number in `0.0..1.0`.
+        fn next_f64(&mut self) -> f64 {
+            (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+        }
+    }
+
+    /// Marsaglia's xorshift64 generator.
+    pub struct XorShift(u64);
+
+    impl XorShift {
+        /// Create a generator. A zero seed is replaced by one, since
+        /// the generator would otherwise only produce zeros.
+        pub fn new(seed: u64) -> XorShift {
+            XorShift(seed.max(1))
+        }
+    }
+
+    impl Rng for XorShift {
+        fn next_u64(&mut self) -> u64 {
+            let mut x = self.0;
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            self.0 = x;
+            x
+        }
+    }
+
+    /// Random selection from a slice, like `rand::seq::SliceRandom`.
+    pub trait SliceRandom {
+        /// The element type of the slice.
+        type Item;
+
+        /// Return a random element, or `None` if the slice is empty.
+        fn choose<R: Rng>(&self, rng: &mut R) -> Option<&Self::Item>;
+
+        /// Return a random element with probability proportional to
+        /// `weight`. Fails if the weights are negative, not finite,
+        /// or sum to zero.
+        fn choose_weighted<R, F>(&self, rng: &mut R, weight: F) -> Result<&Self::Item, ()>
+        where
+            R: Rng,
+            F: Fn(&Self::Item) -> f64;
+    }
+
+    impl<T> SliceRandom for [T] {
+        type Item = T;
+
+        fn choose<R: Rng>(&self, rng: &mut R) -> Option<&T> {
+            if self.is_empty() {
+                None
+            } else {
+                Some(&self[rng.below(self.len())])
+            }
+        }
+
+        fn choose_weighted<R, F>(&self, rng: &mut R, weight: F) -> Result<&T, ()>
+        where
+            R: Rng,
+            F: Fn(&T) -> f64,
+        {
+            let weights = self.iter().map(weight).collect::<Vec<_>>();
+            if weights.iter().any(|w| !w.is_finite() || *w < 0.0) {
+                return Err(());
+            }
+            let total = weights.iter().sum::<f64>();
+            if !(total > 0.0 && total.is_finite()) {
+                return Err(());
+            }
+            let mut pick = rng.next_f64() * total;
+            let mut last = Err(());
+            for (item, &w) in self.iter().zip(&weights) {
+                if w > 0.0 {
+                    if pick < w {
+                        return Ok(item);
+                    }
+                    last = Ok(item);
+                }
+                pick -= w;
+            }
+            // Rounding can leave a sliver past the last weight.
+            last
+        }
+    }
+}
+
+/// Never-ending iterator over words in the Markov chain.
+///
+/// Generated with the [`iter`] or [`iter_from`] methods.
+///
+/// [`iter`]: struct.MarkovChain.html#method.iter
+/// [`iter_from`]: struct.MarkovChain.html#method.iter_from
+pub struct Words<'a, R: Rng> {
+    map: &'a HashMap<Bigram<'a>, Vec<&'a str>>,
+    rng: R,
+    keys: &'a Vec<Bigram<'a>>,
+    starts: &'a Vec<Bigram<'a>>,
+    state: Bigram<'a>,
+    options: Options<'a>,
+    short_run: usize,
+    recent: VecDeque<&'a str>,
+    recent_states: VecDeque<Bigram<'a>>,
+    /// Lowercase words from the blocklist without punctuation.
+    blocked: HashSet<String>,
+    /// Keys and sentence starts without blocked words, computed on
+    /// the first random jump.
+    allowed_keys: Option<Vec<Bigram<'a>>>,
+    allowed_starts: Option<Vec<Bigram<'a>>>,
+    /// Whether a paragraph break came before the last word.
+    paragraph_break: bool,
+}
+
+#[cfg(feature = "rand")]
+impl<'a> Words<'a, ChaCha20Rng> {
+    /// Save the current position of the iterator.
+    ///
+    /// Use [`MarkovChain::resume`] to continue from the saved state.
+    ///
+    /// [`MarkovChain::resume`]: struct.MarkovChain.html#method.resume
+    pub fn save(&self) -> GeneratorState {
+        GeneratorState {
+            state: (self.state.0.to_string(), self.state.1.to_string()),
+            seed: self.rng.get_seed(),
+            stream: self.rng.get_stream(),
+            word_pos: self.rng.get_word_pos(),
+        }
+    }
+}
+
+impl<'a, R: Rng> Words<'a, R> {
+    /// Mark the words which start a sentence.
+    ///
+    /// The returned iterator yields each word together with a flag
+    /// which is `true` for the first word and for every word
+    /// following a word which ends a sentence. These are the words
+    /// which are capitalized when the words are joined into text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("Veni, vidi, vici. Alea iacta est. Veni, vidi, vici.");
+    /// let words = chain
+    ///     .iter_from(("Veni,", "vidi,"))
+    ///     .sentence_starts()
+    ///     .take(5)
+    ///     .collect::<Vec<_>>();
+    /// assert_eq!(
+    ///     words,
+    ///     vec![
+    ///         ("Veni,", true),
+    ///         ("vidi,", false),
+    ///         ("vici.", false),
+    ///         ("Alea", true),
+    ///         ("iacta", false),
+    ///     ]
+    /// );
+    /// ```
+    pub fn sentence_starts(self) -> SentenceStarts<'a, R> {
+        SentenceStarts {
+            words: self,
+            at_start: true,
+        }
+    }
+
+    /// Check if a paragraph break came before the last word.
+    ///
+    /// Chains learned with [`MarkovChain::learn_paragraphs`] know
+    /// where the paragraphs end. The iterator does not produce the
+    /// breaks as words, use this method after [`next`] to find out if
+    /// the returned word starts a new paragraph.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn_paragraphs("One two three.\n\nFour five six.");
+    /// let mut words = chain.iter_from(("One", "two"));
+    /// assert_eq!(words.next(), Some("One"));
+    /// assert_eq!(words.nth(2), Some("Four"));
+    /// assert!(words.after_paragraph_break());
+    /// assert_eq!(words.next(), Some("five"));
+    /// assert!(!words.after_paragraph_break());
+    /// ```
+    ///
+    /// [`MarkovChain::learn_paragraphs`]: struct.MarkovChain.html#method.learn_paragraphs
+    /// [`next`]: #method.next
+    pub fn after_paragraph_break(&self) -> bool {
+        self.paragraph_break
+    }
+
+    /// Write the next sentence to `w`.
+    ///
+    /// The words are joined like with [`MarkovChain::generate`]: the
+    /// first word is capitalized and a `'.'` is added if the chain
+    /// does not end the sentence by itself. The sentence is ended
+    /// explicitly after 50 words. The iterator continues after the
+    /// sentence, so calling this repeatedly writes one sentence after
+    /// the other. Nothing is written if the chain is empty.
+    ///
+    /// The words are streamed to `w` as they are produced, only the
+    /// last word is held back since its punctuation is trimmed if the
+    /// sentence is ended explicitly. The exception is when
+    /// [`Options::max_sentence_chars`] or the French punctuation
+    /// style is used: the sentence is then built in memory first.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from `w` if writing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::MarkovChain;
+    ///
+    /// let mut chain = MarkovChain::new();
+    /// chain.learn("Veni, vidi, vici. Alea iacta est. Veni, vidi, vici.");
+    /// let mut words = chain.iter_from(("Veni,", "vidi,"));
+    /// let mut text = String::new();
+    /// words.write_sentence(&mut text).unwrap();
+    /// assert_eq!(text, "Veni, vidi, vici.");
+    /// text.push(' ');
+    /// words.write_sentence(&mut text).unwrap();
+    /// assert_eq!(text, "Veni, vidi, vici. Alea iacta est.");
+    /// ```
+    ///
+    /// [`MarkovChain::generate`]: struct.MarkovChain.html#method.generate
+    /// [`Options::max_sentence_chars`]: struct.Options.html#structfield.max_sentence_chars
+    pub fn write_sentence<W: std::fmt::Write>(&mut self, w: &mut W) -> std::fmt::Result {
+        if self.options.max_sentence_chars.is_some()
+            || self.options.punctuation_style == PunctuationStyle::French
+        {
+            // These options need to see the whole sentence.
+            let options = self.options.clone();
+            let mut builder = TextBuilder::new(&options);
+            self.push_sentence(&mut builder);
+            return w.write_str(&builder.finish());
+        }
+
+        let mut last: Option<Cow<'_, str>> = None;
+        let mut words = 0;
+        let mut ended = false;
+        while words < SENTENCE_MAX_WORDS && !ended {
+            let word = match self.next() {
+                Some(word) => word,
+                None => break,
+            };
+            if let Some(previous) = last.take() {
+                write_word(w, &previous, words == 1)?;
+                w.write_char(' ')?;
+            }
+            let word = match self.options.truncate_long_words {
+                Some(max_chars) => truncate_word(word, max_chars),
+                None => Cow::Borrowed(word),
+            };
+            ended = (self.options.is_terminator)(&word);
+            last = Some(word);
+            words += 1;
+        }
+
+        match last {
+            Some(word) if ended => write_word(w, &word, words == 1),
+            Some(word) => {
+                let word = word.trim_end_matches(is_ascii_punctuation);
+                write_word(w, word, words == 1)?;
+                w.write_char('.')
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Add words to `builder` until the current sentence ends.
+    ///
+    /// At least one word is added. The sentence is ended explicitly
+    /// if it reaches [`SENTENCE_MAX_WORDS`] words without the chain
+    /// producing a terminator.
+    fn push_sentence(&mut self, builder: &mut TextBuilder<'_>) {
+        self.push_sentence_with_max(builder, SENTENCE_MAX_WORDS);
+    }
+
+    /// Add words to `builder` until the current sentence ends or has
+    /// `max_words` words.
+    fn push_sentence_with_max(&mut self, builder: &mut TextBuilder<'_>, max_words: usize) {
+        while let Some(word) = self.next() {
+            if self.paragraph_break {
+                builder.push_word(PARAGRAPH_BREAK);
+            }
+            builder.push_word(word);
+            if builder.needs_cap {
+                break;
+            }
+            if builder.sentence_words >= max_words {
+                builder.end_sentence();
+                break;
+            }
+        }
+    }
+
+    /// Check if a word is in the blocklist. Punctuation around the
+    /// word and case are ignored.
+    fn is_blocked_word(&self, word: &str) -> bool {
+        if self.blocked.is_empty() {
+            return false;
+        }
+        let word = word.trim_matches(is_ascii_punctuation);
+        if word.chars().any(char::is_uppercase) {
+            self.blocked.contains(&word.to_lowercase())
+        } else {
+            self.blocked.contains(word)
+        }
+    }
+
+    /// Check if a bigram contains a word from the blocklist.
+    fn is_blocked(&self, (a, b): Bigram<'_>) -> bool {
+        self.is_blocked_word(a) || self.is_blocked_word(b)
+    }
+
+    /// The states without blocked words.
+    fn allowed_states(&self, states: &[Bigram<'a>]) -> Vec<Bigram<'a>> {
+        states
+            .iter()
+            .copied()
+            .filter(|&state| !self.is_blocked(state))
+            .collect()
+    }
+
+    /// Choose a random state in the Markov chain, avoiding states with
+    /// blocked words if possible. The chain must not be empty.
+    fn random_key(&mut self) -> Bigram<'a> {
+        if !self.blocked.is_empty() {
+            if self.allowed_keys.is_none() {
+                self.allowed_keys = Some(self.allowed_states(self.keys));
+            }
+            if let Some(&key) = self.allowed_keys.as_ref().unwrap().choose(&mut self.rng) {
+                return key;
+            }
+        }
+        *self.keys.choose(&mut self.rng).unwrap()
+    }
+
+    /// Choose a random sentence start, avoiding starts with blocked
+    /// words if possible. There must be at least one start.
+    fn random_start(&mut self) -> Bigram<'a> {
+        if !self.blocked.is_empty() {
+            if self.allowed_starts.is_none() {
+                self.allowed_starts = Some(self.allowed_states(self.starts));
+            }
+            if let Some(&start) = self.allowed_starts.as_ref().unwrap().choose(&mut self.rng) {
+                return start;
+            }
+        }
+        *self.starts.choose(&mut self.rng).unwrap()
+    }
+
+    /// Choose the next word among the candidates.
+    ///
+    /// Candidates in the blocklist are skipped, unless all candidates
+    /// are blocked.
+    fn choose(&mut self, candidates: &[&'a str]) -> &'a str {
+        let allowed;
+        let mut candidates = candidates;
+        if !self.blocked.is_empty() && candidates.iter().any(|word| !self.is_blocked_word(word)) {
+            allowed = candidates
+                .iter()
+                .copied()
+                .filter(|word| !self.is_blocked_word(word))
+                .collect::<Vec<_>>();
+            candidates = &allowed;
+        }
+
+        let long;
+        if let Some(max_short) = self.options.max_consecutive_short {
+            // The run of short words ending with the next word to be
+            // produced, which is the second word of the state.
+            let run = if is_short_word(self.state.1) {
+                self.short_run + 1
+            } else {
+                0
+            };
+            if run >= max_short && candidates.iter().any(|word| !is_short_word(word)) {
+                long = candidates
+                    .iter()
+                    .copied()
+                    .filter(|word| !is_short_word(word))
+                    .collect::<Vec<_>>();
+                candidates = &long;
+            }
+        }
+
+        let fresh;
+        if self.options.no_repeat_window > 0 {
+            // The second word of the state is produced before the
+            // chosen word, so it belongs to the window too.
+            let window = self.options.no_repeat_window;
+            let skip = self.recent.len().saturating_sub(window - 1);
+            let state = self.state;
+            let recent = &self.recent;
+            let is_recent =
+                |word: &str| word == state.1 || recent.iter().skip(skip).any(|&w| w == word);
+            if candidates.iter().any(|word| !is_recent(word)) {
+                fresh = candidates
+                    .iter()
+                    .copied()
+                    .filter(|word| !is_recent(word))
+                    .collect::<Vec<_>>();
+                candidates = &fresh;
+            }
+        }
+
+        let unvisited;
+        if self.options.no_repeat_state_window > 0 {
+            // The current state was added to the recent states before
+            // choosing its successor.
+            let first = self.state.1;
+            let recent_states = &self.recent_states;
+            let is_recent = |word: &str| recent_states.iter().any(|&state| state == (first, word));
+            if candidates.iter().any(|word| !is_recent(word)) {
+                unvisited = candidates
+                    .iter()
+                    .copied()
+                    .filter(|word| !is_recent(word))
+                    .collect::<Vec<_>>();
+                candidates = &unvisited;
+            }
+        }
+
+        let bias = self.options.word_length_bias;
+        let temperature = self.options.temperature;
+        if temperature <= 0.0 {
+            most_frequent(candidates)
+        } else if temperature != 1.0 && !temperature.is_nan() {
+            let counts = count_words(candidates);
+            // Scale by the largest count to avoid overflow.
+            let max = counts.iter().map(|&(_, count)| count).max().unwrap() as f64;
+            let weighted = counts.choose_weighted(&mut self.rng, |&(word, count)| {
+                (count as f64 / max).powf(1.0 / temperature) * bias.weight(word)
+            });
+            match weighted {
+                Ok(&(word, _)) => word,
+                // The weights can be invalid for extreme temperatures.
+                Err(_) => counts.choose(&mut self.rng).unwrap().0,
+            }
+        } else if bias == WordLengthBias::None {
+            candidates.choose(&mut self.rng).unwrap()
+        } else {
+            candidates
+                .choose_weighted(&mut self.rng, |word| bias.weight(word))
+                .unwrap()
+        }
+    }
+
+    /// Continue from the bigram formed by the last two words of
+    /// `prefix`, skipping past these words. Jump to a random state if
+    /// the bigram is unknown.
+    #[cfg(feature = "rand")]
+    fn skip_prefix(&mut self, prefix: &[&'a str]) {
+        let state = match *prefix {
+            [.., a, b] => self.map.get_key_value(&(a, b)).map(|(&key, _)| key),
+            _ => None,
+        };
+        match state {
+            Some(state) => {
+                self.state = state;
+                self.next();
+                self.next();
+            }
+            None => self.jump(),
+        }
+    }
+
+    /// Jump to a random state in the Markov chain.
+    #[cfg(feature = "rand")]
+    fn jump(&mut self) {
+        if !self.keys.is_empty() {
+            self.state = self.random_key();
+        }
+    }
+}
+
+impl<'a, R: Rng> Iterator for Words<'a, R> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.step(Words::choose)
+    }
+}
+
+impl<'a, R: Rng> Words<'a, R> {
+    /// Produce the next word, using `choose` to select the successor
+    /// of the current state.
+    ///
+    /// Paragraph breaks learned with `learn_paragraphs` are skipped
+    /// and recorded in `paragraph_break` instead.
+    fn step<F>(&mut self, mut choose: F) -> Option<&'a str>
+    where
+        F: FnMut(&mut Self, &'a [&'a str]) -> &'a str,
+    {
+        let mut paragraph_break = false;
+        loop {
+            let word = self.advance(&mut choose)?;
+            if word != PARAGRAPH_BREAK {
+                self.paragraph_break = paragraph_break;
+                return Some(word);
+            }
+            paragraph_break = true;
+        }
+    }
+
+    /// Move to the next state and return the first word of the
+    /// current state, which might be a paragraph break.
+    fn advance<F>(&mut self, choose: F) -> Option<&'a str>
+    where
+        F: FnOnce(&mut Self, &'a [&'a str]) -> &'a str,
+    {
+        if self.map.is_empty() {
+            return None;
+        }
+
+        let result = Some(self.state.0);
+        if self.options.max_consecutive_short.is_some() {
+            self.short_run = if is_short_word(self.state.0) {
+                self.short_run + 1
+            } else {
+                0
+            };
+        }
+        if self.options.no_repeat_window > 0 {
+            if self.recent.len() >= self.options.no_repeat_window {
+                self.recent.pop_front();
+            }
+            self.recent.push_back(self.state.0);
+        }
+        if self.options.no_repeat_state_window > 0 {
+            if self.recent_states.len() >= self.options.no_repeat_state_window {
+                self.recent_states.pop_front();
+            }
+            self.recent_states.push_back(self.state);
+        }
+
+        if !self.starts.is_empty() && !self.map.contains_key(&self.state) {
+            // Restart at the beginning of a learned sentence.
+            self.state = self.random_start();
+            return result;
+        }
+
+        while !self.map.contains_key(&self.state) {
+            self.state = self.random_key();
+        }
+        let next_words = &self.map[&self.state];
+        let next = choose(self, next_words);
+        self.state = (self.state.1, next);
+        result
+    }
+}
+
+/// Strategy for selecting the next word among the successors of a
+/// bigram.
+///
+/// Implement this trait to experiment with other ways of selecting
+/// words, e.g., temperature-scaled selection. Use the selector with
+/// [`MarkovChain::generate_with_selector`].
+///
+/// # Examples
+///
+/// A selector which always picks the most frequent word:
+///
+/// ```
+/// use lipsum::{MarkovChain, Selector};
+/// use rand::Rng;
+///
+/// struct MostFrequent;
+///
+/// impl Selector for MostFrequent {
+///     fn choose<'a>(&mut self, candidates: &[(&'a str, u32)], _rng: &mut impl Rng) -> &'a str {
+///         candidates.iter().max_by_key(|(_, count)| *count).unwrap().0
+///     }
+/// }
+///
+/// let mut chain = MarkovChain::new();
+/// chain.learn("a b c a b c a b d a b c a b");
+/// let text = chain.generate_with_selector(rand::thread_rng(), 20, MostFrequent);
+/// // After the random start, this repeats "a b c".
+/// println!("{}", text);
+/// ```
+///
+/// [`MarkovChain::generate_with_selector`]: struct.MarkovChain.html#method.generate_with_selector
+#[cfg(feature = "rand")]
+pub trait Selector {
+    /// Select one of the candidates.
+    ///
+    /// Each candidate is a distinct word together with the number of
+    /// times it followed the current bigram in the learned text. The
+    /// candidates are listed in the order they were learned and there
+    /// is always at least one candidate.
+    fn choose<'a>(&mut self, candidates: &[(&'a str, u32)], rng: &mut impl Rng) -> &'a str;
+}
+
+/// Selector which picks words with a probability proportional to
+/// their frequency.
+///
+/// This is how words are selected by [`MarkovChain::generate_with_rng`],
+/// though the random numbers are used differently.
+///
+/// [`MarkovChain::generate_with_rng`]: struct.MarkovChain.html#method.generate_with_rng
+#[cfg(feature = "rand")]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct FrequencySelector;
+
+#[cfg(feature = "rand")]
+impl Selector for FrequencySelector {
+    fn choose<'a>(&mut self, candidates: &[(&'a str, u32)], rng: &mut impl Rng) -> &'a str {
+        candidates
+            .choose_weighted(rng, |&(_, count)| count)
+            .unwrap()
+            .0
+    }
+}
+
+/// Count each distinct word in the order of first occurrence.
+fn count_words<'a>(words: &[&'a str]) -> Vec<(&'a str, u32)> {
+    let mut counts: Vec<(&str, u32)> = Vec::new();
+    for &word in words {
+        match counts.iter_mut().find(|(w, _)| *w == word) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((word, 1)),
+        }
+    }
+    counts
+}
+
+/// Collect the word trigrams in `text`, ignoring case and punctuation.
+#[cfg(feature = "rand")]
+fn word_trigrams(text: &str) -> HashSet<String> {
+    let words = text
+        .split_whitespace()
+        .map(|word| word.trim_matches(is_ascii_punctuation).to_lowercase())
+        .collect::<Vec<_>>();
+    words.windows(3).map(|window| window.join(" ")).collect()
+}
+
+/// Fraction of shared trigrams out of the trigrams in the smaller set.
+#[cfg(feature = "rand")]
+fn similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let smaller = a.len().min(b.len());
+    if smaller == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / smaller as f64
+}
+
+/// Choose one of `TERMINATORS` using `weights`. Returns `'.'` if all
+/// weights are zero.
+#[cfg(feature = "rand")]
+fn choose_terminator<R: Rng>(weights: [u32; 3], rng: &mut R) -> char {
+    let total = weights.iter().map(|&weight| u64::from(weight)).sum::<u64>();
+    if total == 0 {
+        return '.';
+    }
+    let mut pick = rng.gen_range(0..total);
+    for (&terminator, &weight) in TERMINATORS.iter().zip(&weights) {
+        if pick < u64::from(weight) {
+            return terminator;
+        }
+        pick -= u64::from(weight);
+    }
+    unreachable!("pick is below the total weight")
+}
+
+/// Append `text` to `out` as a quoted JSON string.
+fn push_json_string(out: &mut String, text: &str) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Cut a word after `max_chars` characters, keeping the trailing
+/// punctuation. At least one character is kept.
+fn truncate_word(word: &str, max_chars: usize) -> Cow<'_, str> {
+    let letters = word.trim_end_matches(is_ascii_punctuation);
+    match letters.char_indices().nth(max_chars.max(1)) {
+        Some((idx, _)) => Cow::Owned(format!("{}{}", &letters[..idx], &word[letters.len()..])),
+        None => Cow::Borrowed(word),
+    }
+}
+
+/// Check if a word is a number for `Options::numbers`.
+#[cfg(feature = "rand")]
+fn is_number(word: &str) -> bool {
+    word.contains(|c: char| c.is_ascii_digit())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_digit() || c.is_ascii_punctuation())
+}
+
+/// Check if a word is short for `Options::max_consecutive_short`.
+/// Punctuation is not counted and markers are never short.
+fn is_short_word(word: &str) -> bool {
+    let letters = word.trim_matches(is_ascii_punctuation);
+    !letters.is_empty()
+        && word != PARAGRAPH_BREAK
+        && letters.chars().count() <= SHORT_WORD_MAX_CHARS
+}
+
+/// The sentence starts of `chain` in sorted order.
+fn sorted_starts<'a>(chain: &MarkovChain<'a>) -> Vec<Bigram<'a>> {
+    let mut starts = chain.starts.clone();
+    starts.sort_unstable();
+    starts
+}
+
+/// Find the most frequent word in a non-empty slice. Ties are broken
+/// by picking the first word in sort order.
+fn most_frequent<'a>(words: &[&'a str]) -> &'a str {
+    let mut sorted = words.to_vec();
+    sorted.sort_unstable();
+
+    let mut best = sorted[0];
+    let mut best_count = 0;
+    let mut i = 0;
+    while i < sorted.len() {
+        let word = sorted[i];
+        let count = sorted[i..].iter().take_while(|&&w| w == word).count();
+        if count > best_count {
+            best = word;
+            best_count = count;
+        }
+        i += count;
+    }
+    best
+}
+
+/// Iterator over words and flags marking sentence starts.
+///
+/// Generated with the [`Words::sentence_starts`] method.
+///
+/// [`Words::sentence_starts`]: struct.Words.html#method.sentence_starts
+pub struct SentenceStarts<'a, R: Rng> {
+    words: Words<'a, R>,
+    at_start: bool,
+}
+
+impl<'a, R: Rng> Iterator for SentenceStarts<'a, R> {
+    type Item = (&'a str, bool);
+
+    fn next(&mut self) -> Option<(&'a str, bool)> {
+        let word = self.words.next()?;
+        let at_start = self.at_start || self.words.after_paragraph_break();
+        self.at_start = (self.words.options.is_terminator)(word);
+        Some((word, at_start))
+    }
+}
+
+/// Check if `c` is an ASCII punctuation character.
+fn is_ascii_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+}
+
+/// Stable 64-bit FNV-1a hasher.
+///
+/// Unlike the hashers in the standard library, the output does not
+/// change between program runs or Rust versions.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Fnv1a {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    /// Write a string followed by a terminating `0xff` byte. The
+    /// byte never occurs in UTF-8 and thus separates the strings.
+    fn write_str(&mut self, s: &str) {
+        self.write(s.as_bytes());
+        self.write(&[0xff]);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Capitalize the first character in a string.
+fn capitalize(word: &str) -> String {
+    let idx = match word.chars().next() {
+        Some(c) => c.len_utf8(),
+        None => 0,
+    };
+
+    let mut result = String::with_capacity(word.len());
+    result.push_str(&word[..idx].to_uppercase());
+    result.push_str(&word[idx..]);
+    result
+}
+
+/// Write a word to `w`, capitalizing the first character if
+/// `capitalize` is set.
+fn write_word<W: std::fmt::Write>(w: &mut W, word: &str, capitalize: bool) -> std::fmt::Result {
+    let mut chars = word.chars();
+    if capitalize {
+        for c in chars.next().into_iter().flat_map(char::to_uppercase) {
+            w.write_char(c)?;
+        }
+    }
+    w.write_str(chars.as_str())
+}
+
+/// Punctuation characters which ends a sentence.
+const TERMINATORS: &[char] = &['.', '!', '?'];
+
+/// Marker for the end of a sentence learned with
+/// `MarkovChain::learn_sentence`.
+const SENTENCE_END: &str = "";
+
+/// Punctuation marks preceded by a space in French typography.
+const FRENCH_SPACED_MARKS: &[char] = &['!', '?', ';', ':'];
+
+/// Space inserted before punctuation marks in French typography.
+const NARROW_NO_BREAK_SPACE: char = '\u{202f}';
+
+/// Marker for a paragraph break learned with
+/// `MarkovChain::learn_paragraphs`.
+const PARAGRAPH_BREAK: &str = "\n\n";
+
+/// Size of the text buffered before it is written in
+/// `MarkovChain::generate_to_writer`.
+#[cfg(feature = "rand")]
+const WRITER_BUFFER_SIZE: usize = 8 * 1024;
+
+/// Maximum number of words in a sentence when generating full
+/// sentences. This avoids endless sentences when the Markov chain
+/// doesn't produce a word which ends a sentence.
+const SENTENCE_MAX_WORDS: usize = 50;
+
+/// Maximum number of characters in a short word, see
+/// `Options::max_consecutive_short`.
+const SHORT_WORD_MAX_CHARS: usize = 3;
+
+/// Minimum diversity required by `MarkovChain::generate_checked`.
+#[cfg(feature = "rand")]
+const MIN_DIVERSITY: f64 = 0.01;
+
+/// Maximum similarity between samples from
+/// `MarkovChain::generate_distinct`.
+#[cfg(feature = "rand")]
+const MAX_DISTINCT_SIMILARITY: f64 = 0.25;
+
+/// Number of attempts at generating each sample in
+/// `MarkovChain::generate_distinct`.
+#[cfg(feature = "rand")]
+const DISTINCT_ATTEMPTS: usize = 10;
+
+/// Incrementally build text out of words.
+///
+/// The first word and every word following a sentence terminator is
+/// capitalized. Sentences can be ended explicitly with
+/// [`TextBuilder::end_sentence`], which will trim trailing punctuation
+/// before adding a `'.'`.
+struct TextBuilder<'o> {
+    options: &'o Options<'o>,
+    text: String,
+    needs_cap: bool,
+    sentence_words: usize,
+    sentence_chars: usize,
+    /// Total number of words added.
+    words: usize,
+    /// Total number of sentences ended.
+    sentences: usize,
+    /// A paragraph break should be added before the next word.
+    paragraph_break: bool,
+    /// Separator added before the next word within a sentence.
+    separator: &'static str,
+    /// Terminator added when the current sentence is ended.
+    terminator: char,
+}
+
+impl<'o> TextBuilder<'o> {
+    fn new(options: &'o Options<'o>) -> TextBuilder<'o> {
+        TextBuilder::with_buffer(options, String::new())
+    }
+
+    /// Build text in `text`, reusing its allocation. The buffer is
+    /// cleared first.
+    fn with_buffer(options: &'o Options<'o>, mut text: String) -> TextBuilder<'o> {
+        text.clear();
+        TextBuilder {
+            options,
+            text,
+            needs_cap: true,
+            sentence_words: 0,
+            sentence_chars: 0,
+            words: 0,
+            sentences: 0,
+            paragraph_break: false,
+            separator: " ",
+            terminator: '.',
+        }
+    }
+
+    /// Add a word, capitalizing it if it starts a new sentence.
+    fn push_word(&mut self, word: &str) {
+        if word == PARAGRAPH_BREAK {
+            // The break is added together with the next word, this
+            // avoids a break at the end of the text.
+            self.paragraph_break = self.options.paragraph_breaks && !self.text.is_empty();
+            return;
+        }
+        if self.paragraph_break {
+            self.paragraph_break = false;
+            self.end_sentence();
+            self.text.push_str(PARAGRAPH_BREAK);
+        }
+
+        let word = match self.options.truncate_long_words {
+            Some(max_chars) => truncate_word(word, max_chars),
+            None => Cow::Borrowed(word),
+        };
+        let word = word.as_ref();
+        let is_terminator = (self.options.is_terminator)(word);
+        let separator = std::mem::replace(&mut self.separator, " ");
+
+        if let Some(max_chars) = self.options.max_sentence_chars {
+            let mut chars = self.sentence_chars + word.chars().count();
+            if self.sentence_chars > 0 {
+                // Room for the separator before the word.
+                chars += separator.chars().count();
+            }
+            if !is_terminator {
+                // Room for a terminator which might be added later.
+                chars += self.terminator_chars();
+            }
+            if self.sentence_chars > 0 && chars > max_chars {
+                self.end_sentence();
+            }
+        }
+
+        if !self.text.is_empty() && !self.text.ends_with(PARAGRAPH_BREAK) {
+            // The sentence might have been ended above.
+            self.text
+                .push_str(if self.needs_cap { " " } else { separator });
+        }
+        if self.sentence_chars > 0 {
+            self.sentence_chars += separator.chars().count();
+        }
+
+        let start = self.text.len();
+        if self.needs_cap {
+            self.text.push_str(&capitalize(word));
+        } else {
+            self.text.push_str(word);
+        }
+        self.sentence_chars += word.chars().count();
+
+        if self.options.punctuation_style == PunctuationStyle::French {
+            let idx = self.text.trim_end_matches(FRENCH_SPACED_MARKS).len();
+            if start < idx && idx < self.text.len() {
+                self.text.insert(idx, NARROW_NO_BREAK_SPACE);
+                self.sentence_chars += 1;
+            }
+        }
+
+        self.words += 1;
+        self.needs_cap = is_terminator;
+        if self.needs_cap {
+            self.sentences += 1;
+            self.sentence_words = 0;
+            self.sentence_chars = 0;
+        } else {
+            self.sentence_words += 1;
+        }
+    }
+
+    /// Add a word like [`push_word`], but first apply the random
+    /// transformations configured in the options.
+    ///
+    /// No random numbers are drawn for disabled transformations.
+    ///
+    /// [`push_word`]: #method.push_word
+    #[cfg(feature = "rand")]
+    fn push_word_with_rng<R: Rng>(&mut self, word: &str, rng: &mut R) {
+        let replaced;
+        let mut word = word;
+        if self.options.numbers != NumberPolicy::Keep && is_number(word) {
+            match &self.options.numbers {
+                NumberPolicy::Keep => {}
+                NumberPolicy::Replace(range) if !range.is_empty() => {
+                    let digits = word.trim_matches(is_ascii_punctuation);
+                    let start = word.len() - word.trim_start_matches(is_ascii_punctuation).len();
+                    replaced = format!(
+                        "{}{}{}",
+                        &word[..start],
+                        rng.gen_range(range.clone()),
+                        &word[start + digits.len()..]
+                    );
+                    word = &replaced;
+                }
+                NumberPolicy::Strip | NumberPolicy::Replace(_) => {
+                    if (self.options.is_terminator)(word) {
+                        self.end_sentence();
+                    }
+                    return;
+                }
+            }
+        }
+
+        let comma_rate = self.options.comma_rate;
+        if comma_rate > 0.0
+            && !self.needs_cap
+            && !self.text.ends_with(is_ascii_punctuation)
+            && rng.gen_bool(comma_rate.min(1.0))
+        {
+            // A trailing comma is removed if the sentence ends here.
+            self.text.push(',');
+            self.sentence_chars += 1;
+        }
+
+        let compound_rate = self.options.compound_rate;
+        if compound_rate > 0.0
+            && !self.needs_cap
+            && !self.text.ends_with(is_ascii_punctuation)
+            && !word.starts_with(is_ascii_punctuation)
+            && !self.last_word().contains('-')
+            && rng.gen_bool(compound_rate.min(1.0))
+        {
+            self.separator = "-";
+        }
+
+        let em_dash_rate = self.options.em_dash_rate;
+        if em_dash_rate > 0.0
+            && self.separator == " "
+            && !self.needs_cap
+            && !self.text.ends_with(is_ascii_punctuation)
+            && !word.starts_with(is_ascii_punctuation)
+            && !(self.options.is_terminator)(word)
+            && rng.gen_bool(em_dash_rate.min(1.0))
+        {
+            self.separator = " — ";
+        }
+
+        let sentences = self.sentences;
+        self.push_word(word);
+
+        if let Some(weights) = self.options.terminator_weights {
+            // Choose a new terminator for each sentence.
+            if self.words == 1 || self.sentences != sentences {
+                self.terminator = choose_terminator(weights, rng);
+            }
+        }
+    }
+
+    /// Number of characters added by `end_sentence`.
+    fn terminator_chars(&self) -> usize {
+        if self.options.punctuation_style == PunctuationStyle::French
+            && FRENCH_SPACED_MARKS.contains(&self.terminator)
+        {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// The last word added.
+    #[cfg(feature = "rand")]
+    fn last_word(&self) -> &str {
+        self.text.rsplit(' ').next().unwrap_or("")
+    }
+
+    /// Ensure the text ends with a sentence terminator. Nothing is
+    /// done if no words have been added since the last terminator.
+    fn end_sentence(&mut self) {
+        if !self.needs_cap {
+            // Trim all trailing punctuation characters to avoid
+            // adding '.' after a ',' or similar.
+            let idx = self
+                .text
+                .trim_end_matches(is_ascii_punctuation)
+                .trim_end_matches(NARROW_NO_BREAK_SPACE)
+                .len();
+            self.text.truncate(idx);
+            if self.terminator_chars() > 1 {
+                self.text.push(NARROW_NO_BREAK_SPACE);
+            }
+            self.text.push(self.terminator);
+            self.sentences += 1;
+        }
+        self.needs_cap = true;
+        self.sentence_words = 0;
+        self.sentence_chars = 0;
+    }
+
+    /// End the current sentence and return the text.
+    fn finish(mut self) -> String {
+        if self.options.terminate {
+            self.end_sentence();
+        }
+        self.text
+    }
+
+    /// End the current sentence and return the text together with
+    /// its metrics.
+    fn finish_measured(mut self) -> Generated {
+        if self.options.terminate {
+            self.end_sentence();
+        }
+        Generated {
+            chars: self.text.chars().count(),
+            text: self.text,
+            words: self.words,
+            sentences: self.sentences,
+        }
+    }
+}
+
+/// Join words from an iterator. The first word is always capitalized
+/// and the generated sentence will end with `'.'` if it doesn't
+/// already end with some other ASCII punctuation character.
+fn join_words<'a, I: Iterator<Item = &'a str>>(words: I, options: &Options) -> String {
+    let mut builder = TextBuilder::new(options);
+    for word in words {
+        builder.push_word(word);
+    }
+    builder.finish()
+}
+
+/// The traditional lorem ipsum text as given in [Wikipedia]. Using
+/// this text alone for a Markov chain of order two doesn't work very
+/// well since each bigram (two consequtive words) is followed by just
+/// one other word. In other words, the Markov chain will always
+/// produce the same output and recreate the lorem ipsum text
+/// precisely. However, combining it with the full text in
+/// [`LIBER_PRIMUS`] works well.
+///
+/// [Wikipedia]: https://en.wikipedia.org/wiki/Lorem_ipsum
+/// [`LIBER_PRIMUS`]: constant.LIBER_PRIMUS.html
+pub const LOREM_IPSUM: &str = include_str!("lorem-ipsum.txt");
+
+/// The first book in Cicero's work De finibus bonorum et malorum ("On
+/// the ends of good and evil"). The lorem ipsum text in
+/// [`LOREM_IPSUM`] is derived from part of this text.
+///
+/// [`LOREM_IPSUM`]: constant.LOREM_IPSUM.html
+pub const LIBER_PRIMUS: &str = include_str!("liber-primus.txt");
+
+/// Split the classic text in [`LOREM_IPSUM`] into its sentences.
+///
+/// The text is split after each `.`, `!`, or `?` and the sentences
+/// are trimmed. The sentences are slices of the constant, so they
+/// keep its line breaks. Use `split_whitespace` to join the lines if
+/// needed.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lorem_ipsum_sentences;
+///
+/// let sentences = lorem_ipsum_sentences();
+/// assert!(sentences[0].starts_with("Lorem ipsum dolor sit amet"));
+/// assert!(sentences[0].ends_with("magna aliqua."));
+/// ```
+///
+/// [`LOREM_IPSUM`]: constant.LOREM_IPSUM.html
+pub fn lorem_ipsum_sentences() -> Vec<&'static str> {
+    LOREM_IPSUM
+        .split_inclusive(TERMINATORS)
+        .map(str::trim)
+        .filter(|sentence| !sentence.is_empty())
+        .collect()
+}
+
+/// Create a Markov chain which has learned the bundled texts.
+///
+/// The chain has learned [`LOREM_IPSUM`] and [`LIBER_PRIMUS`] and is
+/// the chain used by [`lipsum`] and the other free functions. A new
+/// chain is built for each call, which allows you to extend it with
+/// your own text before using it with the full [`MarkovChain`] API.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::{default_chain, lipsum};
+///
+/// let mut chain = default_chain();
+/// assert_eq!(chain.generate_from(7, ("Lorem", "ipsum")), lipsum(7));
+///
+/// chain.learn("Lorem ipsum in nova lingua.");
+/// ```
+///
+/// [`LOREM_IPSUM`]: constant.LOREM_IPSUM.html
+/// [`LIBER_PRIMUS`]: constant.LIBER_PRIMUS.html
+/// [`lipsum`]: fn.lipsum.html
+/// [`MarkovChain`]: struct.MarkovChain.html
+pub fn default_chain() -> MarkovChain<'static> {
+    let mut chain = MarkovChain::new();
+    // The cost of learning increases as more and more text is
+    // added, so we start with the smallest text.
+    chain.learn(LOREM_IPSUM);
+    chain.learn(LIBER_PRIMUS);
+    chain
+}
+
+thread_local! {
+    // Markov chain generating lorem ipsum text.
+    static LOREM_IPSUM_CHAIN: MarkovChain<'static> = default_chain();
+}
+
+/// Generate `n` words of lorem ipsum text. The output will always start with
+/// "Lorem ipsum".
+///
+/// The text continues with the standard lorem ipsum text from [`LOREM_IPSUM`]
+/// and becomes randomly generated but deterministic if more than 18 words is
+/// requested. See [`lipsum_words`] if fully random text is needed.
+///
+/// Without the `rand` feature, the random part of the text is
+/// selected with a built-in generator and differs from the default.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum;
+///
+/// assert_eq!(lipsum(7), "Lorem ipsum dolor sit amet, consectetur adipiscing.");
+/// ```
+///
+/// [`LOREM_IPSUM`]: constant.LOREM_IPSUM.html
+/// [`lipsum_words`]: fn.lipsum_words.html
+pub fn lipsum(n: usize) -> String {
+    LOREM_IPSUM_CHAIN.with(|chain| chain.generate_from(n, ("Lorem", "ipsum")))
+}
+
+/// Generated text together with its metrics.
+///
+/// Returned by [`lipsum_measured`].
+///
+/// [`lipsum_measured`]: fn.lipsum_measured.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Generated {
+    /// The generated text.
+    pub text: String,
+    /// Number of words in the text.
+    pub words: usize,
+    /// Number of characters in the text.
+    pub chars: usize,
+    /// Number of sentences in the text.
+    pub sentences: usize,
+}
+
+/// Generate `n` words of lorem ipsum text and measure it.
+///
+/// The text is the same as the text returned by [`lipsum`]. The
+/// number of words and sentences are counted while the text is
+/// generated, which saves callers from measuring the text again.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_measured;
+///
+/// let generated = lipsum_measured(7);
+/// assert_eq!(generated.text, "Lorem ipsum dolor sit amet, consectetur adipiscing.");
+/// assert_eq!(generated.words, 7);
+/// assert_eq!(generated.chars, 51);
+/// assert_eq!(generated.sentences, 1);
+/// ```
+///
+/// [`lipsum`]: fn.lipsum.html
+pub fn lipsum_measured(n: usize) -> Generated {
+    LOREM_IPSUM_CHAIN.with(|chain| {
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        for word in chain.iter_from(("Lorem", "ipsum")).take(n) {
+            builder.push_word(word);
+        }
+        builder.finish_measured()
+    })
+}
+
+/// Generate roughly `n` bytes of lorem ipsum text.
+///
+/// The text starts with "Lorem ipsum" like the text from [`lipsum`]
+/// and is returned as UTF-8 bytes. Note that `n` is a target and not
+/// an exact length: words are generated until the text is at least
+/// `n` bytes long, after which it is truncated to the last character
+/// boundary at or before `n` bytes. The result is thus at most `n`
+/// bytes long, but it can be a few bytes shorter. The truncation can
+/// happen in the middle of a word.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_bytes;
+///
+/// assert_eq!(lipsum_bytes(11), b"Lorem ipsum");
+/// assert_eq!(lipsum_bytes(1000).len(), 1000);
+/// ```
+///
+/// [`lipsum`]: fn.lipsum.html
+pub fn lipsum_bytes(n: usize) -> Vec<u8> {
+    LOREM_IPSUM_CHAIN.with(|chain| {
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        let mut words = chain.iter_from(("Lorem", "ipsum"));
+        while builder.text.len() < n {
+            match words.next() {
+                Some(word) => builder.push_word(word),
+                None => break,
+            }
+        }
+
+        let mut text = builder.finish();
+        let mut len = n.min(text.len());
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+        text.truncate(len);
+        text.into_bytes()
+    })
+}
+
+/// Generate `n` words of lorem ipsum text with the first character
+/// split off.
+///
+/// The text is the same as the text returned by [`lipsum`], but the
+/// first character is returned separately. This allows templating
+/// engines to style it as a [drop cap]. When `n` is zero, a space is
+/// returned together with an empty string.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_dropcap;
+///
+/// assert_eq!(lipsum_dropcap(3), ('L', String::from("orem ipsum dolor.")));
+/// assert_eq!(lipsum_dropcap(0), (' ', String::new()));
+/// ```
+///
+/// [`lipsum`]: fn.lipsum.html
+/// [drop cap]: https://en.wikipedia.org/wiki/Initial
+pub fn lipsum_dropcap(n: usize) -> (char, String) {
+    let mut text = lipsum(n);
+    if text.is_empty() {
+        return (' ', text);
+    }
+    let first = text.remove(0);
+    (first, text)
+}
+
+/// Generate `n` words of lorem ipsum text, avoiding an allocation
+/// when possible.
+///
+/// This is like [`lipsum`], but returns borrowed text when the result
+/// is known in advance: an empty string when `n` is zero, and the
+/// first generated sentence when `n` is 21, the number of words in
+/// that sentence. The sentence follows [`LOREM_IPSUM`] until "dolore"
+/// and then continues with text from [`LIBER_PRIMUS`]. Other lengths
+/// are returned as an owned `String`.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::{lipsum, lipsum_cow};
+/// use std::borrow::Cow;
+///
+/// assert_eq!(lipsum_cow(0), Cow::Borrowed(""));
+/// assert!(matches!(lipsum_cow(21), Cow::Borrowed(_)));
+/// assert_eq!(lipsum_cow(21), lipsum(21));
+/// assert_eq!(lipsum_cow(5), lipsum(5));
+/// ```
+///
+/// [`lipsum`]: fn.lipsum.html
+/// [`LOREM_IPSUM`]: constant.LOREM_IPSUM.html
+/// [`LIBER_PRIMUS`]: constant.LIBER_PRIMUS.html
+pub fn lipsum_cow(n: usize) -> Cow<'static, str> {
+    if n == 0 {
+        Cow::Borrowed("")
+    } else if n == LIPSUM_OPENING.split(' ').count() {
+        Cow::Borrowed(LIPSUM_OPENING)
+    } else {
+        Cow::Owned(lipsum(n))
+    }
+}
+
+/// Generate `n` words of lorem ipsum text with a custom RNG. The output will
+/// always start with "Lorem ipsum".
+///
+/// A custom RNG allows to base the markov chain on a different random number
+/// sequence. This also allows using a regular [`thread_rng`] random number
+/// generator. If that generator is used, the text will differ in each
+/// invocation.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_with_rng;
+/// use rand::thread_rng;
+///
+/// println!("{}", lipsum_with_rng(thread_rng(), 23));
+/// // -> "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do
+/// //     eiusmod tempor incididunt ut labore et dolore magnam aliquam
+/// //     quaerat voluptatem. Ut enim."
+/// ```
+///
+/// [`thread_rng`]: https://docs.rs/rand/latest/rand/fn.thread_rng.html
+#[cfg(feature = "rand")]
+pub fn lipsum_with_rng(rng: impl Rng, n: usize) -> String {
+    LOREM_IPSUM_CHAIN.with(|chain| chain.generate_with_rng_from(rng, n, ("Lorem", "ipsum")))
+}
+
+/// Generate at least `min_words` words of lorem ipsum text ending
+/// with a complete sentence.
+///
+/// Like [`lipsum`], the text starts with "Lorem ipsum". After
+/// `min_words` words, the generation continues until the current
+/// sentence ends. The text thus has `min_words` words or more, so
+/// this is not suitable for strict length limits. Use [`lipsum`] for
+/// an exact number of words.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_at_least;
+///
+/// assert_eq!(
+///     lipsum_at_least(2),
+///     "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do \
+///      eiusmod tempor incididunt ut labore et dolore magnam aliquam quaerat \
+///      voluptatem."
+/// );
+/// ```
+///
+/// [`lipsum`]: fn.lipsum.html
+#[cfg(feature = "rand")]
+pub fn lipsum_at_least(min_words: usize) -> String {
+    lipsum_at_least_with_rng(default_rng(), min_words)
+}
+
+/// Generate at least `min_words` words of lorem ipsum text ending
+/// with a complete sentence with a custom RNG.
+///
+/// See [`lipsum_at_least`] for details.
+///
+/// [`lipsum_at_least`]: fn.lipsum_at_least.html
+#[cfg(feature = "rand")]
+pub fn lipsum_at_least_with_rng(rng: impl Rng, min_words: usize) -> String {
+    LOREM_IPSUM_CHAIN.with(|chain| {
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        let mut words = chain.iter_with_rng_from(rng, ("Lorem", "ipsum"));
+        while builder.words < min_words {
+            match words.next() {
+                Some(word) => builder.push_word(word),
+                None => break,
+            }
+        }
+        if !builder.needs_cap {
+            words.push_sentence(&mut builder);
+        }
+        builder.finish()
+    })
+}
+
+/// Transformation applied to generated text by [`lipsum_styled`].
+///
+/// [`lipsum_styled`]: fn.lipsum_styled.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transform {
+    /// Leave the text unchanged. This is the default.
+    None,
+    /// Replace letters with similar looking digits, e.g., `"a"` with
+    /// `"4"` and `"e"` with `"3"`. Both lowercase and uppercase
+    /// letters are replaced.
+    Leet,
+    /// Convert the text to uppercase.
+    Uppercase,
+}
+
+impl Transform {
+    /// Apply the transformation to `text`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lipsum::Transform;
+    ///
+    /// assert_eq!(Transform::Leet.apply("Lorem ipsum dolor sit amet."), "L0r3m 1p5um d0l0r 517 4m37.");
+    /// assert_eq!(Transform::Uppercase.apply("Lorem ipsum."), "LOREM IPSUM.");
+    /// ```
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Transform::None => String::from(text),
+            Transform::Leet => text
+                .chars()
+                .map(|c| match c.to_ascii_lowercase() {
+                    'a' => '4',
+                    'e' => '3',
+                    'i' => '1',
+                    'o' => '0',
+                    's' => '5',
+                    't' => '7',
+                    _ => c,
+                })
+                .collect(),
+            Transform::Uppercase => text.to_uppercase(),
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::None
+    }
+}
+
+/// Generate `n` words of lorem ipsum text with a [`Transform`]
+/// applied.
+///
+/// The text is generated like with [`lipsum`] and the transformation
+/// is applied to the final string.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::{lipsum_styled, Transform};
+///
+/// assert_eq!(lipsum_styled(5, Transform::Leet), "L0r3m 1p5um d0l0r 517 4m37.");
+/// ```
+///
+/// [`Transform`]: enum.Transform.html
+/// [`lipsum`]: fn.lipsum.html
+#[cfg(feature = "rand")]
+pub fn lipsum_styled(n: usize, transform: Transform) -> String {
+    lipsum_styled_with_rng(default_rng(), n, transform)
+}
+
+/// Generate `n` words of lorem ipsum text with a [`Transform`]
+/// applied with a custom RNG.
+///
+/// See [`lipsum_styled`] for details.
+///
+/// [`Transform`]: enum.Transform.html
+/// [`lipsum_styled`]: fn.lipsum_styled.html
+#[cfg(feature = "rand")]
+pub fn lipsum_styled_with_rng(rng: impl Rng, n: usize, transform: Transform) -> String {
+    transform.apply(&lipsum_with_rng(rng, n))
+}
+
+/// Generate `n` words of lorem ipsum text.
+///
+/// The text is deterministically sampled from a Markov chain based on
+/// [`LOREM_IPSUM`]. Multiple sentences may be generated, depending on the
+/// punctuation of the words being selected.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_words;
+///
+/// assert_eq!(lipsum_words(6), "Ullus investigandi veri, nisi inveneris, et.");
+/// ```
+///
+/// [`LOREM_IPSUM`]: constant.LOREM_IPSUM.html
+#[cfg(feature = "rand")]
+pub fn lipsum_words(n: usize) -> String {
+    LOREM_IPSUM_CHAIN.with(|chain| chain.generate(n))
+}
+
+/// Generate `n` words of lorem ipsum text with a custom RNG.
+///
+/// A custom RNG allows to base the markov chain on a different random number
+/// sequence. This also allows using a regular [`thread_rng`] random number
+/// generator. If that generator is used, the text will differ in each
+/// invocation.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_words_with_rng;
+/// use rand::thread_rng;
+///
+/// println!("{}", lipsum_words_with_rng(thread_rng(), 7));
+/// // -> "Quot homines, tot sententiae; falli igitur possumus."
+/// ```
+///
+/// [`thread_rng`]: https://docs.rs/rand/latest/rand/fn.thread_rng.html
+#[cfg(feature = "rand")]
+pub fn lipsum_words_with_rng(rng: impl Rng, n: usize) -> String {
+    LOREM_IPSUM_CHAIN.with(|chain| chain.generate_with_rng(rng, n))
+}
+
+/// Generate `n` words of lorem ipsum text with one word per line.
+///
+/// Like [`lipsum`], the text starts with "Lorem ipsum". The words are
+/// stripped of punctuation and joined with `'\n'`. The first word is
+/// capitalized, the other words keep the case from the learned text.
+/// This is useful for testing narrow columns and animations.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_vertical;
+///
+/// assert_eq!(lipsum_vertical(4), "Lorem\nipsum\ndolor\nsit");
+/// ```
+///
+/// [`lipsum`]: fn.lipsum.html
+#[cfg(feature = "rand")]
+pub fn lipsum_vertical(n: usize) -> String {
+    lipsum_vertical_with_rng(default_rng(), n)
+}
+
+/// Generate `n` words of lorem ipsum text with one word per line
+/// using a custom RNG.
+///
+/// See [`lipsum_vertical`] for details.
+///
+/// [`lipsum_vertical`]: fn.lipsum_vertical.html
+#[cfg(feature = "rand")]
+pub fn lipsum_vertical_with_rng(rng: impl Rng, n: usize) -> String {
+    LOREM_IPSUM_CHAIN.with(|chain| {
+        let words = chain
+            .iter_with_rng_from(rng, ("Lorem", "ipsum"))
+            .map(|word| word.trim_matches(is_ascii_punctuation))
+            .filter(|word| !word.is_empty())
+            .take(n);
+
+        let mut text = String::new();
+        for (i, word) in words.enumerate() {
+            if i == 0 {
+                text.push_str(&capitalize(word));
+            } else {
+                text.push('\n');
+                text.push_str(word);
+            }
+        }
+        text
+    })
+}
+
+/// Generate `n` words of lorem ipsum text determined by `key`.
+///
+/// The same key always gives the same text, which is useful for
+/// placeholder text which should be stable for, e.g., a page slug.
+/// The text is generated like with [`lipsum_words_with_rng`], using
+/// a [`ChaCha20Rng`] seeded with [`seed_from_u64`]. The seed is the
+/// 64-bit [FNV-1a] hash of the UTF-8 bytes of `key`. This scheme
+/// will not change, so the text for a given key is stable across
+/// versions as long as the bundled texts stay the same.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_keyed;
+///
+/// let text = lipsum_keyed("/blog/hello-world", 10);
+/// assert_eq!(text, lipsum_keyed("/blog/hello-world", 10));
+/// assert_ne!(text, lipsum_keyed("/blog/goodbye", 10));
+/// ```
+///
+/// [`lipsum_words_with_rng`]: fn.lipsum_words_with_rng.html
+/// [`ChaCha20Rng`]: https://docs.rs/rand_chacha/latest/rand_chacha/struct.ChaCha20Rng.html
+/// [`seed_from_u64`]: https://docs.rs/rand/latest/rand/trait.SeedableRng.html#method.seed_from_u64
+/// [FNV-1a]: https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function
+#[cfg(feature = "rand")]
+pub fn lipsum_keyed(key: &str, n: usize) -> String {
+    let mut hasher = Fnv1a::new();
+    hasher.write(key.as_bytes());
+    lipsum_words_with_rng(ChaCha20Rng::seed_from_u64(hasher.finish()), n)
+}
+
+/// Generate `n` words of lorem ipsum text determined by a numeric
+/// `seed` and a string `salt`.
+///
+/// The same pair of seed and salt always gives the same text, while
+/// changing either gives different text. This is useful in
+/// multi-tenant systems where each tenant uses its own salt to get
+/// stable placeholder text which differs from other tenants.
+///
+/// The two are combined by computing the 64-bit [FNV-1a] hash of the
+/// 8 little-endian bytes of `seed` followed by the UTF-8 bytes of
+/// `salt`. The text is then generated like with
+/// [`lipsum_words_with_rng`], using a [`ChaCha20Rng`] seeded with
+/// [`seed_from_u64`] and the hash. This scheme will not change.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_seeded_salted;
+///
+/// let text = lipsum_seeded_salted(42, "tenant-a", 10);
+/// assert_eq!(text, lipsum_seeded_salted(42, "tenant-a", 10));
+/// assert_ne!(text, lipsum_seeded_salted(42, "tenant-b", 10));
+/// assert_ne!(text, lipsum_seeded_salted(43, "tenant-a", 10));
+/// ```
+///
+/// [`lipsum_words_with_rng`]: fn.lipsum_words_with_rng.html
+/// [`ChaCha20Rng`]: https://docs.rs/rand_chacha/latest/rand_chacha/struct.ChaCha20Rng.html
+/// [`seed_from_u64`]: https://docs.rs/rand/latest/rand/trait.SeedableRng.html#method.seed_from_u64
+/// [FNV-1a]: https://en.wikipedia.org/wiki/Fowler%E2%80%93Noll%E2%80%93Vo_hash_function
+#[cfg(feature = "rand")]
+pub fn lipsum_seeded_salted(seed: u64, salt: &str, n: usize) -> String {
+    let mut hasher = Fnv1a::new();
+    hasher.write(&seed.to_le_bytes());
+    hasher.write(salt.as_bytes());
+    lipsum_words_with_rng(ChaCha20Rng::seed_from_u64(hasher.finish()), n)
+}
+
+/// The first sentence generated by `lipsum`. It ends with a
+/// terminator from the text, so it is a prefix of all longer output.
+const LIPSUM_OPENING: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, \
+                              sed do eiusmod tempor incididunt ut labore et dolore magnam \
+                              aliquam quaerat voluptatem.";
+
+/// The traditional opening sentence of lorem ipsum text.
+#[cfg(feature = "rand")]
+const CLASSIC_OPENING: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, \
+                               sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.";
+
+/// The lead of paragraphs following the first classic paragraph.
+#[cfg(feature = "rand")]
+const CLASSIC_LEAD: &str = "Lorem ipsum dolor sit amet,";
+
+/// Number of sentences in each classic lorem ipsum paragraph.
+#[cfg(feature = "rand")]
+const CLASSIC_PARAGRAPH_SENTENCES: usize = 5;
+
+/// Generate `n` paragraphs of classic lorem ipsum text.
+///
+/// The first paragraph starts with the full traditional opening
+/// sentence "Lorem ipsum dolor sit amet, consectetur adipiscing elit,
+/// sed do eiusmod tempor incididunt ut labore et dolore magna
+/// aliqua." The following paragraphs start with "Lorem ipsum dolor
+/// sit amet," and all paragraphs continue with random text from
+/// [`LOREM_IPSUM`] and [`LIBER_PRIMUS`]. Each paragraph has five
+/// sentences and the paragraphs are separated by a blank line.
+///
+/// The text is deterministic, use [`lipsum_classic_paragraphs_with_rng`]
+/// to get random text.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_classic_paragraphs;
+///
+/// let text = lipsum_classic_paragraphs(3);
+/// let paragraphs = text.split("\n\n").collect::<Vec<_>>();
+/// assert_eq!(paragraphs.len(), 3);
+/// assert!(paragraphs[0].starts_with("Lorem ipsum dolor sit amet, consectetur"));
+/// assert!(paragraphs[1].starts_with("Lorem ipsum dolor sit amet,"));
+/// ```
+///
+/// [`LOREM_IPSUM`]: constant.LOREM_IPSUM.html
+/// [`LIBER_PRIMUS`]: constant.LIBER_PRIMUS.html
+/// [`lipsum_classic_paragraphs_with_rng`]: fn.lipsum_classic_paragraphs_with_rng.html
+#[cfg(feature = "rand")]
+pub fn lipsum_classic_paragraphs(n: usize) -> String {
+    lipsum_classic_paragraphs_with_rng(default_rng(), n)
+}
+
+/// Generate `n` paragraphs of classic lorem ipsum text using the
+/// specified random number generator.
+///
+/// See [`lipsum_classic_paragraphs`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_classic_paragraphs_with_rng;
+/// use rand::thread_rng;
+///
+/// println!("{}", lipsum_classic_paragraphs_with_rng(thread_rng(), 2));
+/// ```
+///
+/// [`lipsum_classic_paragraphs`]: fn.lipsum_classic_paragraphs.html
+#[cfg(feature = "rand")]
+pub fn lipsum_classic_paragraphs_with_rng(rng: impl Rng, n: usize) -> String {
+    classic_paragraphs(rng, n, usize::MAX)
+}
+
+/// Generate up to `n` paragraphs of classic lorem ipsum text with at
+/// most `max_total_words` words in total.
+///
+/// The paragraphs are generated like in
+/// [`lipsum_classic_paragraphs_with_rng`], but generation stops before
+/// the first sentence which would bring the total number of words
+/// above `max_total_words`. The last paragraph is thus cut at a
+/// sentence boundary and may be shorter than the others. An empty
+/// string is returned if not even the first sentence fits.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_classic_paragraphs_with_max_words;
+/// use rand::thread_rng;
+///
+/// let text = lipsum_classic_paragraphs_with_max_words(thread_rng(), 10, 100);
+/// assert!(text.split_whitespace().count() <= 100);
+/// assert!(text.ends_with(&['.', '!', '?'][..]));
+/// ```
+///
+/// [`lipsum_classic_paragraphs_with_rng`]: fn.lipsum_classic_paragraphs_with_rng.html
+#[cfg(feature = "rand")]
+pub fn lipsum_classic_paragraphs_with_max_words(
+    rng: impl Rng,
+    n: usize,
+    max_total_words: usize,
+) -> String {
+    classic_paragraphs(rng, n, max_total_words)
+}
+
+/// Generate up to `n` classic paragraphs, stopping before the
+/// sentence which would exceed `max_words` words in total.
+#[cfg(feature = "rand")]
+fn classic_paragraphs(rng: impl Rng, n: usize, max_words: usize) -> String {
+    LOREM_IPSUM_CHAIN.with(|chain| {
+        let options = Options::new();
+        let mut words = chain.iter_with_rng(rng);
+        let mut paragraphs = Vec::with_capacity(n);
+        let mut total = 0;
+        for i in 0..n {
+            let lead = if i == 0 {
+                CLASSIC_OPENING
+            } else {
+                CLASSIC_LEAD
+            };
+            let lead = lead.split_whitespace().collect::<Vec<_>>();
+
+            let mut builder = TextBuilder::new(&options);
+            let mut full = false;
+            for j in 0..CLASSIC_PARAGRAPH_SENTENCES {
+                let (len, count, sentences) =
+                    (builder.text.len(), builder.words, builder.sentences);
+                if j == 0 {
+                    for word in &lead {
+                        builder.push_word(word);
+                    }
+                    words.skip_prefix(&lead);
+                    if !builder.needs_cap {
+                        // Complete the sentence started by the lead.
+                        words.push_sentence(&mut builder);
+                    }
+                } else {
+                    words.push_sentence(&mut builder);
+                }
+                if total + builder.words > max_words {
+                    // Drop the sentence which did not fit.
+                    builder.text.truncate(len);
+                    builder.words = count;
+                    builder.sentences = sentences;
+                    full = true;
+                    break;
+                }
+            }
+
+            if builder.words > 0 {
+                total += builder.words;
+                paragraphs.push(builder.finish());
+            }
+            if full {
+                break;
+            }
+        }
+        paragraphs.join("\n\n")
+    })
+}
+
+/// Generate a document with the given section headings, each
+/// followed by `paragraphs_each` paragraphs of lorem ipsum text.
+///
+/// The headings are copied verbatim, which makes it easy to mock up
+/// long documents such as a privacy policy with realistic headings.
+/// Each paragraph has five sentences of random text. Headings and
+/// paragraphs are separated by a blank line.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_document;
+///
+/// let document = lipsum_document(&["1. Introduction", "2. Data We Collect"], 2);
+/// assert!(document.starts_with("1. Introduction\n\n"));
+/// assert_eq!(document.split("\n\n").count(), 2 + 2 * 2);
+/// ```
+#[cfg(feature = "rand")]
+pub fn lipsum_document(sections: &[&str], paragraphs_each: usize) -> String {
+    lipsum_document_with_rng(default_rng(), sections, paragraphs_each)
+}
+
+/// Generate a document with section headings using a custom RNG.
+///
+/// See [`lipsum_document`] for the layout of the document.
+///
+/// [`lipsum_document`]: fn.lipsum_document.html
+#[cfg(feature = "rand")]
+pub fn lipsum_document_with_rng(
+    rng: impl Rng,
+    sections: &[&str],
+    paragraphs_each: usize,
+) -> String {
+    LOREM_IPSUM_CHAIN.with(|chain| {
+        let options = Options::new();
+        let mut words = chain.iter_with_rng(rng);
+        let mut blocks = Vec::with_capacity(sections.len() * (1 + paragraphs_each));
+        for heading in sections {
+            blocks.push(heading.to_string());
+            for _ in 0..paragraphs_each {
+                let mut builder = TextBuilder::new(&options);
+                for _ in 0..CLASSIC_PARAGRAPH_SENTENCES {
+                    words.push_sentence(&mut builder);
+                }
+                blocks.push(builder.finish());
+            }
+        }
+        blocks.join("\n\n")
+    })
+}
+
+/// Truncate text to at most `max_chars` characters, cutting it at a
+/// sentence boundary.
+///
+/// The result is the longest prefix of `text` which ends with `.`,
+/// `!`, or `?` followed by whitespace or the end of the text, and
+/// which has at most `max_chars` characters. The length is measured
+/// in `char`s, not bytes, and multi-byte characters are never split.
+/// An empty string is returned if no sentence fits.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::truncate_to_sentence;
+///
+/// let text = "Lorem ipsum. Dolor sit amet! Consectetur?";
+/// assert_eq!(truncate_to_sentence(text, 30), "Lorem ipsum. Dolor sit amet!");
+/// assert_eq!(truncate_to_sentence(text, 20), "Lorem ipsum.");
+/// assert_eq!(truncate_to_sentence(text, 5), "");
+/// ```
+pub fn truncate_to_sentence(text: &str, max_chars: usize) -> &str {
+    let mut end = 0;
+    let mut chars = text.char_indices().take(max_chars).peekable();
+    while let Some((idx, c)) = chars.next() {
+        if TERMINATORS.contains(&c) {
+            let next = match chars.peek() {
+                Some(&(_, next)) => Some(next),
+                None => text[idx + c.len_utf8()..].chars().next(),
+            };
+            if next.map_or(true, char::is_whitespace) {
+                end = idx + c.len_utf8();
+            }
+        }
+    }
+    &text[..end]
+}
+
+/// Generate a single capitalized lorem ipsum word.
+///
+/// Punctuation is stripped from the word. The word is picked from the
+/// Markov chain using a fixed seed, so this is deterministic and
+/// always returns the same word. Use [`lipsum_word_with_rng`] with
+/// [`thread_rng`] to get a different word on each call.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_word;
+///
+/// assert_eq!(lipsum_word(), "Ullus");
+/// assert_eq!(lipsum_word(), "Ullus");
+/// ```
+///
+/// [`lipsum_word_with_rng`]: fn.lipsum_word_with_rng.html
+/// [`thread_rng`]: https://docs.rs/rand/latest/rand/fn.thread_rng.html
+#[cfg(feature = "rand")]
+pub fn lipsum_word() -> String {
+    lipsum_word_with_rng(default_rng())
+}
+
+/// Generate a single capitalized lorem ipsum word with a custom RNG.
+///
+/// A custom RNG allows to base the markov chain on a different random number
+/// sequence. This also allows using a regular [`thread_rng`] random number
+/// generator. If that generator is used, the word will differ in each
+/// invocation.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_word_with_rng;
+/// use rand::thread_rng;
+///
+/// println!("{}", lipsum_word_with_rng(thread_rng()));
+/// // -> "Voluptatem"
+/// ```
+///
+/// [`thread_rng`]: https://docs.rs/rand/latest/rand/fn.thread_rng.html
+#[cfg(feature = "rand")]
+pub fn lipsum_word_with_rng(rng: impl Rng) -> String {
+    LOREM_IPSUM_CHAIN.with(|chain| {
+        chain
+            .iter_with_rng(rng)
+            .map(|word| word.trim_matches(is_ascii_punctuation))
+            .find(|word| !word.is_empty())
+            .map(capitalize)
+            .unwrap_or_default()
+    })
+}
+
+/// Minimum number of words to include in a title.
+#[cfg(feature = "rand")]
+const TITLE_MIN_WORDS: usize = 3;
+/// Maximum number of words to include in a title.
+#[cfg(feature = "rand")]
+const TITLE_MAX_WORDS: usize = 8;
+/// Words shorter than this size are not capitalized.
+#[cfg(feature = "rand")]
+const TITLE_SMALL_WORD: usize = 3;
+
+/// Generate a short lorem ipsum text with words in title case.
+///
+/// The words are capitalized and stripped for punctuation characters.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_title;
+///
+/// println!("{}", lipsum_title());
+/// ```
+///
+/// This will generate a string like
+///
+/// > Grate Meminit et Praesentibus
+///
+/// which should be suitable for use in a document title for section
+/// heading.
+#[cfg(feature = "rand")]
+pub fn lipsum_title() -> String {
+    lipsum_title_with_rng(default_rng())
+}
+
+/// Generate a short lorem ipsum text with words in title case with a custom RNG.
+///
+/// A custom RNG allows to base the markov chain on a different random number
+/// sequence. This also allows using a regular [`thread_rng`] random number
+/// generator. If that generator is used, the text will differ in each
+/// invocation.
+///
+/// The words are capitalized and stripped for punctuation characters.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_title_with_rng;
+/// use rand::thread_rng;
+///
+/// println!("{}", lipsum_title_with_rng(thread_rng()));
+/// ```
+///
+/// This will generate a string like
+///
+/// > Grate Meminit et Praesentibus
+///
+/// which should be suitable for use in a document title for section
+/// heading.
+///
+/// [`thread_rng`]: https://docs.rs/rand/latest/rand/fn.thread_rng.html
+#[cfg(feature = "rand")]
+pub fn lipsum_title_with_rng(rng: impl Rng) -> String {
+    lipsum_title_styled_with_rng(rng, TitleStyle::Title)
+}
+
+/// Generate `n` lorem ipsum words with every word capitalized.
+///
+/// The words are stripped for punctuation characters and
+/// capitalized, regardless of their length. Unlike [`lipsum_title`],
+/// short words are thus capitalized too. This is useful for labels
+/// and buttons in user interface mockups.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_startcase;
+///
+/// assert_eq!(
+///     lipsum_startcase(8),
+///     "Ullus Investigandi Veri Nisi Inveneris Et Quaerendi Defatigatio"
+/// );
+/// ```
+///
+/// [`lipsum_title`]: fn.lipsum_title.html
+#[cfg(feature = "rand")]
+pub fn lipsum_startcase(n: usize) -> String {
+    lipsum_startcase_with_rng(default_rng(), n)
+}
+
+/// Generate `n` lorem ipsum words with every word capitalized with a
+/// custom RNG.
+///
+/// See [`lipsum_startcase`] for details.
+///
+/// [`lipsum_startcase`]: fn.lipsum_startcase.html
+#[cfg(feature = "rand")]
+pub fn lipsum_startcase_with_rng(rng: impl Rng, n: usize) -> String {
+    LOREM_IPSUM_CHAIN.with(|chain| {
+        chain
+            .iter_with_rng(rng)
+            .map(|word| word.trim_matches(is_ascii_punctuation))
+            .filter(|word| !word.is_empty())
+            .take(n)
+            .map(capitalize)
+            .collect::<Vec<_>>()
+            .join(" ")
+    })
+}
+
+/// Capitalization style for [`lipsum_title_styled`].
+///
+/// [`lipsum_title_styled`]: fn.lipsum_title_styled.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TitleStyle {
+    /// Capitalize the first word and all long words, like
+    /// [`lipsum_title`]. This is the default.
+    ///
+    /// [`lipsum_title`]: fn.lipsum_title.html
+    Title,
+    /// Capitalize only the first word and write the remaining words
+    /// in lowercase.
+    Sentence,
+}
+
+impl Default for TitleStyle {
+    fn default() -> Self {
+        TitleStyle::Title
+    }
+}
+
+/// Generate a short lorem ipsum title capitalized in the given style.
+///
+/// With [`TitleStyle::Title`], this is the same as [`lipsum_title`].
+/// With [`TitleStyle::Sentence`], only the first word is capitalized.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::{lipsum_title_styled, TitleStyle};
+///
+/// println!("{}", lipsum_title_styled(TitleStyle::Sentence));
+/// ```
+///
+/// This will generate a string like
+///
+/// > Grate meminit et praesentibus
+///
+/// [`TitleStyle::Title`]: enum.TitleStyle.html#variant.Title
+/// [`TitleStyle::Sentence`]: enum.TitleStyle.html#variant.Sentence
+/// [`lipsum_title`]: fn.lipsum_title.html
+#[cfg(feature = "rand")]
+pub fn lipsum_title_styled(style: TitleStyle) -> String {
+    lipsum_title_styled_with_rng(default_rng(), style)
+}
+
+/// Generate a short lorem ipsum title capitalized in the given style
+/// with a custom RNG.
+///
+/// See [`lipsum_title_styled`] for details.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::{lipsum_title_styled_with_rng, TitleStyle};
+/// use rand::SeedableRng;
+/// use rand_chacha::ChaCha20Rng;
+///
+/// let rng = ChaCha20Rng::seed_from_u64(0);
+/// let title = lipsum_title_styled_with_rng(rng, TitleStyle::Sentence);
+/// assert!(title.starts_with(char::is_uppercase));
+/// assert!(!title.split(' ').skip(1).any(|w| w.contains(char::is_uppercase)));
+/// ```
+///
+/// [`lipsum_title_styled`]: fn.lipsum_title_styled.html
+#[cfg(feature = "rand")]
+pub fn lipsum_title_styled_with_rng(mut rng: impl Rng, style: TitleStyle) -> String {
+    LOREM_IPSUM_CHAIN.with(|chain| {
+        let n = rng.gen_range(TITLE_MIN_WORDS..TITLE_MAX_WORDS);
+        // The average word length with our corpus is 7.6 bytes so
+        // this capacity will avoid most allocations.
+        let mut title = String::with_capacity(8 * n);
+
+        let words = chain
+            .iter_with_rng(rng)
+            .map(|word| word.trim_matches(is_ascii_punctuation))
+            .filter(|word| !word.is_empty())
+            .take(n);
+
+        for (i, word) in words.enumerate() {
+            if i > 0 {
+                title.push(' ');
+            }
+
+            // Capitalize the first word and, in title case, all long words.
+            if i == 0 || (style == TitleStyle::Title && word.len() > TITLE_SMALL_WORD) {
+                title.push_str(&capitalize(word));
+            } else if style == TitleStyle::Sentence {
+                title.push_str(&word.to_lowercase());
+            } else {
+                title.push_str(word);
+            }
+        }
+        title
+    })
+}
+
+/// Generate a numbered outline with `sections` entries on each level
+/// and `depth` levels.
+///
+/// Each line holds a title generated like [`lipsum_title`], prefixed
+/// by its hierarchical number. Nested lines are indented by two
+/// spaces per level. Top-level numbers end with a `.`:
+///
+/// ```
+/// use lipsum::lipsum_outline;
+///
+/// let outline = lipsum_outline(2, 2);
+/// assert_eq!(outline.lines().count(), 2 + 2 * 2);
+/// println!("{}", outline);
+/// ```
+///
+/// This prints
+///
+/// ```text
+/// 1. Poterit ne Illud Quidem Adduci
+///   1.1 Te Quoquo Modo
+///   1.2 Venire ut Abhorreant a Latinis Quod
+/// 2. Erant Illa Torquatis Numquam hoc ita
+///   2.1 Iniucundus Labor Iis Igitur est
+///   2.2 De Voluptate et Dolore Magnam Aliquam
+/// ```
+///
+/// [`lipsum_title`]: fn.lipsum_title.html
+#[cfg(feature = "rand")]
+pub fn lipsum_outline(sections: usize, depth: usize) -> String {
+    lipsum_outline_with_rng(default_rng(), sections, depth)
+}
+
+/// Generate a numbered outline with a custom RNG.
+///
+/// See [`lipsum_outline`] for the format of the outline. A custom RNG
+/// allows to base the markov chain on a different random number
+/// sequence. This also allows using a regular [`thread_rng`] random
+/// number generator. If that generator is used, the titles will
+/// differ in each invocation.
+///
+/// [`lipsum_outline`]: fn.lipsum_outline.html
+/// [`thread_rng`]: https://docs.rs/rand/latest/rand/fn.thread_rng.html
+#[cfg(feature = "rand")]
+pub fn lipsum_outline_with_rng(mut rng: impl Rng, sections: usize, depth: usize) -> String {
+    let mut lines = Vec::new();
+    push_outline(&mut rng, sections, depth, "", &mut lines);
+    lines.join("\n")
+}
+
+/// Add `sections` numbered titles below `prefix` to `lines`,
+/// recursing until `depth` reaches zero.
+#[cfg(feature = "rand")]
+fn push_outline<R: Rng>(
+    rng: &mut R,
+    sections: usize,
+    depth: usize,
+    prefix: &str,
+    lines: &mut Vec<String>,
+) {
+    if depth == 0 {
+        return;
+    }
+    let indent = "  ".repeat(prefix.matches('.').count());
+    for i in 1..=sections {
+        let number = format!("{}{}", prefix, i);
+        let title = lipsum_title_with_rng(&mut *rng);
+        if prefix.is_empty() {
+            lines.push(format!("{}{}. {}", indent, number, title));
+        } else {
+            lines.push(format!("{}{} {}", indent, number, title));
+        }
+        push_outline(rng, sections, depth - 1, &format!("{}.", number), lines);
+    }
+}
+
+/// Generate a table of contents with `entries` headings.
+///
+/// Each entry is a heading level and a title generated like
+/// [`lipsum_title`]. Levels start at 1 and are at most `max_depth`.
+/// The first entry is at level 1 and each following entry is at most
+/// one level deeper than the previous entry, just like headings in a
+/// real document. The entries are not formatted, which lets you
+/// render them however you like. No entries are generated if
+/// `max_depth` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_toc;
+///
+/// for (level, title) in lipsum_toc(5, 3) {
+///     println!("{} {}", "#".repeat(level as usize), title);
+/// }
+/// ```
+///
+/// [`lipsum_title`]: fn.lipsum_title.html
+#[cfg(feature = "rand")]
+pub fn lipsum_toc(entries: usize, max_depth: usize) -> Vec<(u8, String)> {
+    lipsum_toc_with_rng(default_rng(), entries, max_depth)
+}
+
+/// Generate a table of contents with a custom RNG.
+///
+/// See [`lipsum_toc`] for the structure of the entries.
+///
+/// [`lipsum_toc`]: fn.lipsum_toc.html
+#[cfg(feature = "rand")]
+pub fn lipsum_toc_with_rng(
+    mut rng: impl Rng,
+    entries: usize,
+    max_depth: usize,
+) -> Vec<(u8, String)> {
+    let max_depth = max_depth.min(usize::from(u8::MAX)) as u8;
+    if max_depth == 0 {
+        return Vec::new();
+    }
+
+    let mut toc = Vec::with_capacity(entries);
+    let mut level: u8 = 1;
+    for i in 0..entries {
+        if i > 0 {
+            level = rng.gen_range(1..=max_depth.min(level.saturating_add(1)));
+        }
+        toc.push((level, lipsum_title_with_rng(&mut rng)));
+    }
+    toc
+}
+
+/// Generate a grid of short placeholder texts with `rows` rows and
+/// `cols` columns.
+///
+/// Each cell holds an independent title generated like
+/// [`lipsum_title`]. This is useful for mocking up tables and card
+/// layouts.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_grid;
+///
+/// let grid = lipsum_grid(2, 3);
+/// assert_eq!(grid.len(), 2);
+/// assert_eq!(grid[0].len(), 3);
+/// for row in &grid {
+///     println!("{}", row.join(" | "));
+/// }
+/// ```
+///
+/// [`lipsum_title`]: fn.lipsum_title.html
+#[cfg(feature = "rand")]
+pub fn lipsum_grid(rows: usize, cols: usize) -> Vec<Vec<String>> {
+    lipsum_grid_with_rng(default_rng(), rows, cols)
+}
+
+/// Generate a grid of short placeholder texts with a custom RNG.
+///
+/// See [`lipsum_grid`] for details.
+///
+/// [`lipsum_grid`]: fn.lipsum_grid.html
+#[cfg(feature = "rand")]
+pub fn lipsum_grid_with_rng(mut rng: impl Rng, rows: usize, cols: usize) -> Vec<Vec<String>> {
+    (0..rows)
+        .map(|_| (0..cols).map(|_| lipsum_title_with_rng(&mut rng)).collect())
+        .collect()
+}
+
+/// Generate a block of lorem ipsum text which fits in `lines` lines
+/// of `cols` columns.
+///
+/// Like [`lipsum`], the text starts with "Lorem ipsum". The words are
+/// wrapped greedily and the text stops with the last word which fits
+/// in the block, so no line is longer than `cols` characters and
+/// there are at most `lines` lines. A word longer than `cols`
+/// characters is cut to fit on a line of its own. This is handy for
+/// mocking up terminal user interfaces with monospace text.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_block;
+///
+/// assert_eq!(
+///     lipsum_block(30, 4),
+///     "Lorem ipsum dolor sit amet,\n\
+///      consectetur adipiscing elit,\n\
+///      sed do eiusmod tempor\n\
+///      incididunt ut labore et dolore"
+/// );
+/// ```
+///
+/// [`lipsum`]: fn.lipsum.html
+#[cfg(feature = "rand")]
+pub fn lipsum_block(cols: usize, lines: usize) -> String {
+    lipsum_block_with_rng(default_rng(), cols, lines)
+}
+
+/// Generate a block of lorem ipsum text which fits in `lines` lines
+/// of `cols` columns with a custom RNG.
+///
+/// See [`lipsum_block`] for details.
+///
+/// [`lipsum_block`]: fn.lipsum_block.html
+#[cfg(feature = "rand")]
+pub fn lipsum_block_with_rng(rng: impl Rng, cols: usize, lines: usize) -> String {
+    if cols == 0 || lines == 0 {
+        return String::new();
+    }
+    // Every word takes at least one column plus a space, so this is
+    // enough words to fill the block.
+    let text = lipsum_with_rng(rng, lines * (cols + 1) / 2);
+    wrap_words(&text, cols, lines).join("\n")
+}
+
+/// Wrap the words in `text` greedily into at most `max_lines` lines
+/// of at most `cols` characters. Words longer than `cols` are cut.
+/// The words which do not fit are dropped. `cols` must be positive.
+#[cfg(feature = "rand")]
+fn wrap_words(text: &str, cols: usize, max_lines: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line_width = 0;
+    for word in text.split_whitespace() {
+        let word = match word.char_indices().nth(cols) {
+            Some((idx, _)) => &word[..idx],
+            None => word,
+        };
+        let width = word.chars().count();
+        match lines.last_mut() {
+            Some(line) if line_width + 1 + width <= cols => {
+                line.push(' ');
+                line.push_str(word);
+                line_width += 1 + width;
+            }
+            _ => {
+                if lines.len() == max_lines {
+                    break;
+                }
+                lines.push(String::from(word));
+                line_width = width;
+            }
+        }
+    }
+    lines
+}
+
+/// Style of comment markers for [`lipsum_comment`].
+///
+/// [`lipsum_comment`]: fn.lipsum_comment.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CommentStyle {
+    /// Line comments, where each line starts with `// `. This is the
+    /// default.
+    Line,
+    /// The inside of a block comment, where each line starts with
+    /// ` * `. The `/*` and `*/` delimiters are not included.
+    Block,
+}
+
+#[cfg(feature = "rand")]
+impl CommentStyle {
+    /// Marker at the start of each line.
+    fn prefix(self) -> &'static str {
+        match self {
+            CommentStyle::Line => "// ",
+            CommentStyle::Block => " * ",
+        }
+    }
+}
+
+impl Default for CommentStyle {
+    fn default() -> Self {
+        CommentStyle::Line
+    }
+}
+
+/// Maximum width of the lines generated by `lipsum_comment`,
+/// including the comment marker.
+#[cfg(feature = "rand")]
+const COMMENT_WIDTH: usize = 80;
+
+/// Generate `words` words of lorem ipsum text formatted as a code
+/// comment.
+///
+/// Like [`lipsum`], the text starts with "Lorem ipsum". The text is
+/// wrapped into lines of at most 80 characters and each line starts
+/// with the comment marker of the [`CommentStyle`]. This is handy for
+/// testing documentation renderers and syntax highlighters.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::{lipsum_comment, CommentStyle};
+///
+/// assert_eq!(
+///     lipsum_comment(CommentStyle::Line, 20),
+///     "// Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod\n\
+///      // tempor incididunt ut labore et dolore magnam aliquam quaerat."
+/// );
+/// ```
+///
+/// [`lipsum`]: fn.lipsum.html
+/// [`CommentStyle`]: enum.CommentStyle.html
+#[cfg(feature = "rand")]
+pub fn lipsum_comment(style: CommentStyle, words: usize) -> String {
+    lipsum_comment_with_rng(default_rng(), style, words)
+}
+
+/// Generate `words` words of lorem ipsum text formatted as a code
+/// comment with a custom RNG.
+///
+/// See [`lipsum_comment`] for details.
+///
+/// [`lipsum_comment`]: fn.lipsum_comment.html
+#[cfg(feature = "rand")]
+pub fn lipsum_comment_with_rng(rng: impl Rng, style: CommentStyle, words: usize) -> String {
+    let prefix = style.prefix();
+    let text = lipsum_with_rng(rng, words);
+    let lines = wrap_words(&text, COMMENT_WIDTH - prefix.len(), usize::MAX);
+    let mut comment = String::with_capacity(text.len() + lines.len() * (prefix.len() + 1));
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            comment.push('\n');
+        }
+        comment.push_str(prefix);
+        comment.push_str(line);
+    }
+    comment
+}
+
+/// Minimum number of words in a fragment from `lipsum_messages`.
+#[cfg(feature = "rand")]
+const FRAGMENT_MIN_WORDS: usize = 2;
+/// Maximum number of words in a fragment from `lipsum_messages`.
+#[cfg(feature = "rand")]
+const FRAGMENT_MAX_WORDS: usize = 4;
+
+/// Generate `count` short messages of lorem ipsum text.
+///
+/// Each message is randomly either a full sentence or a fragment of
+/// two to four words without punctuation, like the messages in a chat
+/// log. Fragments are capitalized, but have no terminator. This is
+/// handy for mocking up chat and messaging user interfaces.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_messages;
+///
+/// let messages = lipsum_messages(5);
+/// assert_eq!(messages.len(), 5);
+/// for message in &messages {
+///     println!("> {}", message);
+/// }
+/// ```
+#[cfg(feature = "rand")]
+pub fn lipsum_messages(count: usize) -> Vec<String> {
+    lipsum_messages_with_rng(default_rng(), count)
+}
+
+/// Generate `count` short messages of lorem ipsum text with a custom
+/// RNG.
+///
+/// See [`lipsum_messages`] for details.
+///
+/// [`lipsum_messages`]: fn.lipsum_messages.html
+#[cfg(feature = "rand")]
+pub fn lipsum_messages_with_rng(rng: impl Rng, count: usize) -> Vec<String> {
+    LOREM_IPSUM_CHAIN.with(|chain| {
+        let options = Options::new();
+        let mut words = chain.iter_with_rng(rng);
+        let mut messages = Vec::with_capacity(count);
+        for _ in 0..count {
+            if words.rng.gen_bool(0.5) {
+                let mut builder = TextBuilder::new(&options);
+                words.push_sentence(&mut builder);
+                messages.push(builder.finish());
+            } else {
+                let n = words.rng.gen_range(FRAGMENT_MIN_WORDS..=FRAGMENT_MAX_WORDS);
+                let fragment = words
+                    .by_ref()
+                    .map(|word| word.trim_matches(is_ascii_punctuation))
+                    .filter(|word| !word.is_empty())
+                    .take(n)
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                messages.push(capitalize(&fragment));
+            }
+        }
+        messages
+    })
+}
+
+/// Maximum number of words in the sentence of a quote.
+#[cfg(feature = "rand")]
+const QUOTE_MAX_WORDS: usize = 15;
+
+/// Generate a short quoted sentence with a fake attribution.
+///
+/// The sentence is wrapped in curly quotes and followed by an em-dash
+/// and a two-word name, where each word is generated like
+/// [`lipsum_word`]. This is handy for mocking up testimonials.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_quote;
+///
+/// println!("{}", lipsum_quote());
+/// ```
+///
+/// This will generate a string like
+///
+/// > “Utens nihil quieti videre, nihil tranquilli potest.” — Quae Vitae
+///
+/// [`lipsum_word`]: fn.lipsum_word.html
+#[cfg(feature = "rand")]
+pub fn lipsum_quote() -> String {
+    lipsum_quote_with_rng(default_rng())
+}
+
+/// Generate a short quoted sentence with a fake attribution with a
+/// custom RNG.
+///
+/// See [`lipsum_quote`] for the format.
+///
+/// [`lipsum_quote`]: fn.lipsum_quote.html
+#[cfg(feature = "rand")]
+pub fn lipsum_quote_with_rng(mut rng: impl Rng) -> String {
+    let sentence = LOREM_IPSUM_CHAIN.with(|chain| {
+        let options = Options::new();
+        let mut builder = TextBuilder::new(&options);
+        chain
+            .iter_with_rng(&mut rng)
+            .push_sentence_with_max(&mut builder, QUOTE_MAX_WORDS);
+        builder.finish()
+    });
+    let first = lipsum_word_with_rng(&mut rng);
+    let last = lipsum_word_with_rng(&mut rng);
+    format!("“{}” — {} {}", sentence, first, last)
+}
+
+/// Generate `sentences` sentences of lorem ipsum text as [SSML].
+///
+/// Like [`lipsum`], the text starts with "Lorem ipsum". The text is
+/// wrapped in a `<speak>` element with each sentence in an `<s>`
+/// element. A `<break>` element is placed between the sentences as a
+/// hint for a pause. XML special characters in the text are escaped.
+/// This is handy for testing text-to-speech engines with placeholder
+/// text.
+///
+/// This function is only available with the `ssml` feature.
+///
+/// # Examples
+///
+/// ```
+/// use lipsum::lipsum_ssml;
+///
+/// let ssml = lipsum_ssml(2);
+/// assert!(ssml.starts_with("<speak><s>Lorem ipsum dolor sit amet,"));
+/// assert_eq!(ssml.matches("<s>").count(), 2);
+/// assert_eq!(ssml.matches("<break").count(), 1);
+/// ```
+///
+/// [SSML]: https://www.w3.org/TR/speech-synthesis11/
+/// [`lipsum`]: fn.lipsum.html
+#[cfg(feature = "ssml")]
+pub fn lipsum_ssml(sentences: usize) -> String {
+    lipsum_ssml_with_rng(default_rng(), sentences)
+}
+
+/// Generate `sentences` sentences of lorem ipsum text as SSML with a
+/// custom RNG.
+///
+/// See [`lipsum_ssml`] for the format.
+///
+/// [`lipsum_ssml`]: fn.lipsum_ssml.html
+#[cfg(feature = "ssml")]
+pub fn lipsum_ssml_with_rng(rng: impl Rng, sentences: usize) -> String {
+    LOREM_IPSUM_CHAIN.with(|chain| {
+        let options = Options::new();
+        let mut words = chain.iter_with_rng_from(rng, ("Lorem", "ipsum"));
+        let mut ssml = String::from("<speak>");
+        for i in 0..sentences {
+            if i > 0 {
+                ssml.push_str(SSML_BREAK);
+            }
+            let mut builder = TextBuilder::new(&options);
+            words.push_sentence(&mut builder);
+            ssml.push_str("<s>");
+            push_xml_escaped(&mut ssml, &builder.finish());
+            ssml.push_str("</s>");
+        }
+        ssml.push_str("</speak>");
+        ssml
+    })
+}
+
+/// Pause inserted between sentences by `lipsum_ssml`.
+#[cfg(feature = "ssml")]
+const SSML_BREAK: &str = "<break strength=\"medium\"/>";
+
+/// Append `text` to `out`, escaping XML special characters.
+#[cfg(feature = "ssml")]
+fn push_xml_escaped(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+#[cfg(all(test, not(feature = "rand")))]
+mod minimal_tests {
+    use super::*;
+
+    #[test]
+    fn lipsum_without_rand() {
+        assert_eq!(lipsum(0), "");
+        assert_eq!(
+            lipsum(7),
+            "Lorem ipsum dolor sit amet, consectetur adipiscing."
+        );
+        for n in [10, 100, 1000] {
+            let text = lipsum(n);
+            assert!(text.starts_with("Lorem ipsum dolor"), "{:?}", text);
+            assert_eq!(text.split_whitespace().count(), n);
+            assert!(text.ends_with(TERMINATORS), "{:?}", text);
+        }
+        assert_eq!(lipsum(100), lipsum(100));
+    }
+}
+
+#[cfg(all(test, feature = "rand"))]
+mod tests {
+    use super::*;
+    use rand::thread_rng;
+
+    #[test]
+    fn starts_with_lorem_ipsum() {
+        assert_eq!(&lipsum(10)[..11], "Lorem ipsum");
+    }
+
+    #[test]
+    fn generate_zero_words() {
+        assert_eq!(lipsum(0).split_whitespace().count(), 0);
+    }
+
+    #[test]
+    fn generate_one_word() {
+        assert_eq!(lipsum(1).split_whitespace().count(), 1);
+    }
+
+    #[test]
+    fn generate_two_words() {
+        assert_eq!(lipsum(2).split_whitespace().count(), 2);
+    }
+
+    #[test]
+    fn starts_differently() {
+        // Check that calls to lipsum_words don't always start with
+        // "Lorem ipsum".
+        let idx = "Lorem ipsum".len();
+        assert_ne!(
+            &lipsum_words_with_rng(thread_rng(), 5)[..idx],
+            &lipsum_words_with_rng(thread_rng(), 5)[..idx]
+        );
+    }
+
+    #[test]
+    fn lipsum_word_varies() {
+        let words = (0..20)
+            .map(|_| lipsum_word_with_rng(thread_rng()))
+            .collect::<HashSet<_>>();
+        assert!(words.len() > 1);
+        for word in words {
+            assert!(word.starts_with(char::is_uppercase));
+            assert!(!word.contains(char::is_whitespace));
+            assert!(!word.ends_with(is_ascii_punctuation));
+        }
+    }
+
+    #[test]
+    fn generate_title() {
+        for word in lipsum_title().split_whitespace() {
+            assert!(
+                !word.starts_with(is_ascii_punctuation) && !word.ends_with(is_ascii_punctuation),
+                "Unexpected punctuation: {:?}",
+                word
+            );
+            if word.len() > TITLE_SMALL_WORD {
+                assert!(
+                    word.starts_with(char::is_uppercase),
+                    "Expected small word to be capitalized: {:?}",
+                    word
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn generate_title_sentence_style() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..20 {
+            let title = lipsum_title_styled_with_rng(&mut rng, TitleStyle::Sentence);
+            let mut words = title.split(' ');
+            assert!(words.next().unwrap().starts_with(char::is_uppercase));
+            for word in words {
+                assert_eq!(word, word.to_lowercase(), "in {:?}", title);
+            }
+        }
+    }
+
+    #[test]
+    fn title_style_default() {
+        let title = lipsum_title_with_rng(ChaCha20Rng::seed_from_u64(0));
+        let styled =
+            lipsum_title_styled_with_rng(ChaCha20Rng::seed_from_u64(0), TitleStyle::default());
+        assert_eq!(title, styled);
+    }
+
+    #[test]
+    fn outline_numbering() {
+        let outline = lipsum_outline_with_rng(thread_rng(), 3, 3);
+        let numbers = outline
+            .lines()
+            .map(|line| line.split_whitespace().next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(numbers.len(), 3 + 3 * 3 + 3 * 3 * 3);
+        assert_eq!(
+            &numbers[..6],
+            ["1.", "1.1", "1.1.1", "1.1.2", "1.1.3", "1.2"]
+        );
+        assert_eq!(numbers.last(), Some(&"3.3.3"));
+
+        for line in outline.lines() {
+            let number = line.split_whitespace().next().unwrap();
+            let level = number.trim_end_matches('.').matches('.').count();
+            assert!(line.starts_with(&format!("{}{}", "  ".repeat(level), number)));
+        }
+
+        assert_eq!(lipsum_outline(5, 0), "");
+        assert_eq!(lipsum_outline(0, 5), "");
+    }
+
+    #[test]
+    fn capitalize_after_punctiation() {
+        // The Markov Chain will yield a "habitut." as the second word. However,
+        // the following "voluptatem" is not capitalized, which does not make
+        // much sense, given that it appears after a full stop. The `join_words`
+        // must ensure that every word appearing after sentence-ending
+        // punctuation is capitalized.
+        assert_eq!(
+            lipsum_words_with_rng(ChaCha20Rng::seed_from_u64(5), 9),
+            "Nullam habuit. Voluptatem cum summum bonum in voluptate est."
+        );
+    }
+
+    #[test]
+    fn generate_targeted_length() {
+        // Remove sentence terminators so that all sentence breaks
+        // come from the target length.
+        let text = LIBER_PRIMUS.replace(TERMINATORS, "");
+        let mut chain = MarkovChain::new();
+        chain.learn(&text);
+
+        for target in [5, 10, 15] {
+            let rng = ChaCha20Rng::seed_from_u64(42);
+            let text = chain.generate_targeted_length(rng, 5000, target);
+            let sentences = text.matches(TERMINATORS).count();
+            let mean = 5000.0 / sentences as f64;
+            assert!(
+                (mean - target as f64).abs() < target as f64 * 0.1,
+                "Mean sentence length {} too far from {}",
+                mean,
+                target
+            );
+        }
+    }
+
+    #[test]
+    fn resume_generation() {
+        LOREM_IPSUM_CHAIN.with(|chain| {
+            let rng = ChaCha20Rng::seed_from_u64(8);
+            let expected = chain
+                .iter_with_rng(rng.clone())
+                .take(100)
+                .collect::<Vec<_>>();
+
+            let mut words = chain.iter_with_rng(rng);
+            let mut result = words.by_ref().take(40).collect::<Vec<_>>();
+            let saved = words.save();
+
+            result.extend(chain.resume(&saved).unwrap().take(60));
+            assert_eq!(result, expected);
+        });
+    }
+
+    #[test]
+    fn resume_unknown_state() {
+        let mut chain = MarkovChain::new();
+        chain.learn("foo bar baz");
+        let saved = GeneratorState {
+            state: (String::from("xxx"), String::from("yyy")),
+            seed: [0; 32],
+            stream: 0,
+            word_pos: 0,
+        };
+        assert!(chain.resume(&saved).is_none());
+    }
+
+    #[test]
+    fn generate_with_sentence_prefix() {
+        let text = LOREM_IPSUM_CHAIN.with(|chain| {
+            let rng = ChaCha20Rng::seed_from_u64(5);
+            chain.generate_with_sentence_prefix(rng, 20, "Lorem ipsum")
+        });
+        let sentences = text.split_inclusive(TERMINATORS).collect::<Vec<_>>();
+        assert_eq!(sentences.len(), 20);
+        for sentence in sentences {
+            assert!(
+                sentence.trim_start().starts_with("Lorem ipsum "),
+                "Unexpected sentence: {:?}",
+                sentence
+            );
+        }
+    }
+
+    #[test]
+    fn generate_with_unknown_sentence_prefix() {
+        let mut chain = MarkovChain::new();
+        chain.learn("foo bar baz. foo bar qux.");
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_sentence_prefix(rng, 3, "hello world");
+        assert_eq!(text.matches("Hello world ").count(), 3);
+    }
+
+    #[test]
+    fn generate_bounded() {
+        LOREM_IPSUM_CHAIN.with(|chain| {
+            let rng = ChaCha20Rng::seed_from_u64(3);
+            let text = chain.generate_bounded(rng.clone(), 1_000_000_000, 25);
+            assert_eq!(text, chain.generate_with_rng(rng.clone(), 25));
+            assert_eq!(text.split_whitespace().count(), 25);
+            assert!(text.ends_with(TERMINATORS));
+
+            assert_eq!(
+                chain.generate_bounded(rng.clone(), 10, 25),
+                chain.generate_with_rng(rng, 10)
+            );
+        });
+    }
+
+    #[test]
+    fn char_level_chain() {
+        let mut chain = MarkovChain::new();
+        chain.learn_char_level("abcabd");
+        assert_eq!(chain.len(), 3);
+        assert_eq!(chain.words(("a", "b")), Some(&vec!["c", "d"]));
+        assert_eq!(chain.words(("b", "c")), Some(&vec!["a"]));
+        assert_eq!(chain.words(("c", "a")), Some(&vec!["b"]));
+
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_chars(rng, 100);
+        assert_eq!(text.len(), 100);
+        assert!(text.chars().all(|c| "abcd".contains(c)));
+        // A "ca" is always followed by "b".
+        for (idx, _) in text.match_indices("ca") {
+            assert!(matches!(text.get(idx + 2..idx + 3), Some("b") | None));
+        }
+    }
+
+    #[test]
+    fn generate_plain() {
+        let rng = ChaCha20Rng::seed_from_u64(11);
+        let text = LOREM_IPSUM_CHAIN.with(|chain| chain.generate_plain(rng, 1000));
+        assert_eq!(text.split(' ').count(), 1000);
+        assert!(!text.contains(|c: char| c.is_ascii_punctuation()));
+        assert!(!text.contains(char::is_uppercase));
+
+        let mut chain = MarkovChain::new();
+        chain.learn("!! ?? .. --");
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(chain.generate_plain(rng, 3), "");
+    }
+
+    #[test]
+    fn generate_paragraphs_seeded() {
+        LOREM_IPSUM_CHAIN.with(|chain| {
+            let document = chain.generate_paragraphs_seeded(1234, 4, 30);
+            assert_eq!(document, chain.generate_paragraphs_seeded(1234, 4, 30));
+            assert_ne!(document, chain.generate_paragraphs_seeded(1235, 4, 30));
+
+            // Paragraph i is generated with seed 1234 + i.
+            let rng = ChaCha20Rng::seed_from_u64(1236);
+            assert_eq!(document[2], chain.generate_with_rng(rng, 30));
+            assert_ne!(document[0], document[1]);
+        });
+    }
+
+    #[test]
+    fn max_sentence_chars() {
+        let options = Options::new().max_sentence_chars(40);
+        let rng = ChaCha20Rng::seed_from_u64(7);
+        let text = LOREM_IPSUM_CHAIN.with(|chain| chain.generate_with_options(rng, 500, &options));
+        assert_eq!(text.split_whitespace().count(), 500);
+        for sentence in text.split_inclusive(TERMINATORS) {
+            let sentence = sentence.trim();
+            assert!(
+                sentence.chars().count() <= 40,
+                "Sentence too long: {:?}",
+                sentence
+            );
+        }
+    }
+
+    #[test]
+    fn truncate_to_sentence_multi_byte() {
+        let text = "Æblegrød på én gang? Ja, søde løgn. Øl!";
+        assert_eq!(truncate_to_sentence(text, 19), "");
+        assert_eq!(truncate_to_sentence(text, 20), "Æblegrød på én gang?");
+        assert_eq!(
+            truncate_to_sentence(text, 35),
+            "Æblegrød på én gang? Ja, søde løgn."
+        );
+        assert_eq!(truncate_to_sentence(text, 39), text);
+        assert_eq!(truncate_to_sentence(text, 100), text);
+        // Terminators inside words are not sentence boundaries.
+        assert_eq!(truncate_to_sentence("Pi is 3.14 or so", 100), "");
+        for max_chars in 0..text.chars().count() {
+            truncate_to_sentence(text, max_chars);
+        }
+    }
+
+    #[test]
+    fn custom_terminator() {
+        let mut chain = MarkovChain::new();
+        chain.learn("alpha beta— gamma delta— epsilon zeta eta— theta alpha beta—");
+        let is_terminator = |word: &str| word.ends_with('—');
+        let options = Options::new().is_terminator(&is_terminator);
+        let rng = ChaCha20Rng::seed_from_u64(2);
+        let text = chain.generate_with_options(rng, 50, &options);
+
+        let mut words = text.split(' ');
+        let mut prev = words.next().unwrap();
+        assert!(prev.starts_with(char::is_uppercase));
+        for word in words {
+            assert_eq!(
+                prev.ends_with('—'),
+                word.starts_with(char::is_uppercase),
+                "Unexpected capitalization: {:?} {:?}",
+                prev,
+                word
+            );
+            prev = word;
+        }
+    }
+
+    #[test]
+    fn empty_chain() {
+        let chain = MarkovChain::new();
+        assert_eq!(chain.generate(10), "");
+    }
+
+    #[test]
+    fn generate_from() {
+        let mut chain = MarkovChain::new();
+        chain.learn("red orange yellow green blue indigo violet");
+        assert_eq!(
+            chain.generate_from(5, ("orange", "yellow")),
+            "Orange yellow green blue indigo."
+        );
+    }
+
+    #[test]
+    fn generate_last_bigram() {
+        // The bigram "yyy zzz" will not be present in the Markov
+        // chain's map, and so we will not generate "xxx yyy zzz" as
+        // one would expect. The chain moves from state "xxx yyy" to
+        // "yyy zzz", but sees that as invalid state and resets itself
+        // back to "xxx yyy".
+        let mut chain = MarkovChain::new();
+        chain.learn("xxx yyy zzz");
+        assert_ne!(chain.generate_from(3, ("xxx", "yyy")), "xxx yyy zzz");
+    }
+
+    #[test]
+    fn generate_from_no_panic() {
+        // No panic when asked to generate a chain from a starting
+        // point that doesn't exist in the chain.
+        let mut chain = MarkovChain::new();
+        chain.learn("foo bar baz");
+        chain.generate_from(3, ("xxx", "yyy"));
+    }
+
+    #[test]
+    fn chain_map() {
+        let mut chain = MarkovChain::new();
+        chain.learn("foo bar baz quuz");
+        let map = &chain.map;
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&("foo", "bar")], vec!["baz"]);
+        assert_eq!(map[&("bar", "baz")], vec!["quuz"]);
+    }
+
+    #[test]
+    fn learn_new_states() {
+        let mut chain = MarkovChain::new();
+        assert_eq!(chain.learn("a b c d e"), 3);
+        assert_eq!(chain.learn("a b c d e"), 0);
+        assert_eq!(chain.learn("c d e f"), 1);
+        assert_eq!(chain.learn("x"), 0);
+        assert_eq!(chain.len(), 4);
+        assert_eq!(chain.keys, [("a", "b"), ("b", "c"), ("c", "d"), ("d", "e")]);
+    }
+
+    #[test]
+    fn learn_order_keeps_keys() {
+        // The texts share bigrams with different successors, so only
+        // the order of the successor lists depends on the learning
+        // order.
+        let texts = ["a b c d", "b c e", "a b x", "c e a b"];
+        let mut ordered = MarkovChain::new();
+        for text in &texts {
+            ordered.learn(text);
+        }
+
+        for i in 0..texts.len() {
+            let mut reordered = MarkovChain::new();
+            for text in texts.iter().skip(i).chain(texts.iter().take(i)).rev() {
+                reordered.learn(text);
+            }
+            assert_eq!(reordered.keys, ordered.keys);
+            assert_eq!(reordered, ordered);
+        }
+
+        let mut batched = MarkovChain::new();
+        batched.learn_all(&texts[2..]);
+        batched.learn_all(&texts[..2]);
+        assert_eq!(batched.keys, ordered.keys);
+        assert_eq!(batched, ordered);
+    }
+
+    #[test]
+    fn learn_all() {
+        let mut all = MarkovChain::new();
+        assert_eq!(all.learn_all(&["a b c", "b c d", "x y"]), 2);
+
+        let mut separate = MarkovChain::new();
+        separate.learn("a b c");
+        separate.learn("b c d");
+        separate.learn("x y");
+
+        assert_eq!(all, separate);
+        assert_eq!(all.keys, separate.keys);
+        assert_eq!(all.text_starts, separate.text_starts);
+        assert_eq!(all.word_count, separate.word_count);
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(
+            all.generate_with_rng(rng.clone(), 10),
+            separate.generate_with_rng(rng, 10)
+        );
+    }
+
+    #[test]
+    fn relearn() {
+        let mut chain = MarkovChain::new();
+        chain.learn_sentence("one two three four");
+        chain.learn("a b c d e");
+        chain.relearn("x y z x y w");
+
+        let mut fresh = MarkovChain::new();
+        fresh.learn("x y z x y w");
+
+        assert_eq!(chain, fresh);
+        assert_eq!(chain.keys, fresh.keys);
+        assert_eq!(chain.starts, fresh.starts);
+        assert_eq!(chain.text_starts, fresh.text_starts);
+        assert_eq!(chain.word_count, fresh.word_count);
+        assert_eq!(chain.sentence_count, fresh.sentence_count);
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(
+            chain.generate_with_rng(rng.clone(), 10),
+            fresh.generate_with_rng(rng, 10)
+        );
+    }
+
+    #[test]
+    fn learn_clean() {
+        let mut messy = MarkovChain::new();
+        messy.learn_clean(
+            "Lorem\tipsum  dolor\u{0}sit\r\namet,\u{200b}consectetur\u{7f}\u{feff}elit.",
+        );
+        let mut clean = MarkovChain::new();
+        clean.learn("Lorem ipsum dolor sit amet, consectetur elit.");
+
+        assert_eq!(
+            messy.edges().collect::<Vec<_>>(),
+            clean.edges().collect::<Vec<_>>()
+        );
+        assert_eq!(messy.estimate_sentences(7), clean.estimate_sentences(7));
+    }
+
+    #[test]
+    fn edges() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c a b d");
+        assert_eq!(
+            chain.edges().collect::<Vec<_>>(),
+            vec![
+                (("a", "b"), "c"),
+                (("a", "b"), "d"),
+                (("b", "c"), "a"),
+                (("c", "a"), "b"),
+            ]
+        );
+    }
+
+    #[test]
+    fn distinct_words() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b a c");
+        assert_eq!(chain.distinct_words(), 3);
+
+        chain.learn("a b d");
+        assert_eq!(chain.distinct_words(), 4);
+    }
+
+    #[test]
+    fn estimate_sentences() {
+        let mut chain = MarkovChain::new();
+        assert_eq!(chain.estimate_sentences(10), 1.0);
+
+        chain.learn("a b c d");
+        assert_eq!(chain.estimate_sentences(0), 0.0);
+        assert_eq!(chain.estimate_sentences(10), 1.0);
+
+        // Three sentences in 16 words in total.
+        chain.learn("e f g h. i j k l. m n o p.");
+        assert_eq!(chain.estimate_sentences(100), 100.0 * 3.0 / 16.0);
+    }
+
+    #[test]
+    fn can_terminate() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c d e, f g a b d");
+        for &state in &chain.keys {
+            assert!(!chain.can_terminate(state));
+        }
+
+        chain.learn("f g h!");
+        assert!(chain.can_terminate(("a", "b")));
+        assert!(!chain.can_terminate(("x", "y")));
+    }
+
+    #[test]
+    fn generate_biased() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c a b extraordinary a b d a b e");
+        let count_long = |bias| {
+            let rng = ChaCha20Rng::seed_from_u64(42);
+            let options = Options::new().word_length_bias(bias);
+            chain
+                .iter_with_options(rng, &options)
+                .take(3000)
+                .filter(|&word| word == "extraordinary")
+                .count()
+        };
+
+        let uniform = count_long(WordLengthBias::None);
+        assert!(count_long(WordLengthBias::Longer) > 2 * uniform);
+        assert!(count_long(WordLengthBias::Shorter) < uniform / 2);
+    }
+
+    #[test]
+    fn fingerprint() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c");
+        chain.learn("a b d");
+        let mut reordered = MarkovChain::new();
+        reordered.learn("a b d");
+        reordered.learn("a b c");
+        assert_eq!(chain.fingerprint(), reordered.fingerprint());
+        assert_eq!(chain.fingerprint(), chain.clone().fingerprint());
+
+        let mut other = MarkovChain::new();
+        other.learn("a b c");
+        other.learn("a b c");
+        assert_ne!(chain.fingerprint(), other.fingerprint());
+
+        // Words are separated and cannot run into each other.
+        let mut split = MarkovChain::new();
+        split.learn("ab c d");
+        let mut joined = MarkovChain::new();
+        joined.learn("a bc d");
+        assert_ne!(split.fingerprint(), joined.fingerprint());
+    }
+
+    #[test]
+    fn generate_into() {
+        let mut chain = MarkovChain::new();
+        chain.learn(LOREM_IPSUM);
+        let mut buf = String::from("old content");
+        for seed in 0..5 {
+            chain.generate_into(ChaCha20Rng::seed_from_u64(seed), 20, &mut buf);
+            assert_eq!(
+                buf,
+                chain.generate_with_rng(ChaCha20Rng::seed_from_u64(seed), 20)
+            );
+        }
+
+        chain.generate_into(ChaCha20Rng::seed_from_u64(0), 0, &mut buf);
+        assert_eq!(buf, "");
+    }
+
+    #[test]
+    fn lipsum_classic_paragraphs() {
+        let text = super::lipsum_classic_paragraphs(4);
+        let paragraphs = text.split("\n\n").collect::<Vec<_>>();
+        assert_eq!(paragraphs.len(), 4);
+        assert!(paragraphs[0].starts_with(CLASSIC_OPENING));
+        for paragraph in &paragraphs[1..] {
+            assert!(paragraph.starts_with(CLASSIC_LEAD));
+        }
+        for paragraph in &paragraphs {
+            assert!(paragraph.ends_with(TERMINATORS));
+        }
+        assert_eq!(super::lipsum_classic_paragraphs(0), "");
+    }
+
+    #[test]
+    fn retain() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b 1 a b c a b 2 c d");
+        let has_digit = |word: &str| word.contains(|c: char| c.is_ascii_digit());
+        chain.retain(|&(a, b), word| !has_digit(a) && !has_digit(b) && !has_digit(word));
+
+        assert_eq!(
+            chain.edges().collect::<Vec<_>>(),
+            vec![(("a", "b"), "c"), (("b", "c"), "a"), (("c", "a"), "b")]
+        );
+        assert_eq!(chain.keys, vec![("a", "b"), ("b", "c"), ("c", "a")]);
+        assert_eq!(
+            chain.generate_with_rng_from(thread_rng(), 4, ("a", "b")),
+            "A b c a."
+        );
+    }
+
+    #[test]
+    fn generate_greedy() {
+        let mut chain = MarkovChain::new();
+        assert_eq!(chain.generate_greedy(5, ("a", "b")), "");
+
+        chain.learn("a b c a b d a b c");
+        assert_eq!(chain.generate_greedy(7, ("a", "b")), "A b c a b c a.");
+
+        // Ties are broken by sort order and dead ends continue from
+        // the first bigram.
+        chain.learn("x y z x y w");
+        assert_eq!(chain.generate_greedy(5, ("x", "y")), "X y b c a.");
+    }
+
+    #[test]
+    fn iter_deterministic() {
+        let mut chain = MarkovChain::new();
+        assert_eq!(chain.iter_deterministic(("a", "b")).next(), None);
+
+        chain.learn(LOREM_IPSUM);
+        chain.learn(LIBER_PRIMUS);
+        let first = chain.iter_deterministic(("Lorem", "ipsum")).take(200);
+        let second = chain.iter_deterministic(("Lorem", "ipsum")).take(200);
+        assert!(first.eq(second));
+
+        // Unknown bigrams continue from the first bigram.
+        let words = chain
+            .iter_deterministic(("foo", "bar"))
+            .take(3)
+            .collect::<Vec<_>>();
+        assert_eq!(words[..2], ["foo", chain.keys[0].1]);
+    }
+
+    #[test]
+    fn most_frequent() {
+        assert_eq!(super::most_frequent(&["b", "a", "b", "c"]), "b");
+        assert_eq!(super::most_frequent(&["c", "b", "a"]), "a");
+        assert_eq!(super::most_frequent(&["c", "c", "a", "a"]), "a");
+    }
+
+    #[test]
+    fn lipsum_cow() {
+        assert!(matches!(super::lipsum_cow(0), Cow::Borrowed("")));
+        assert!(matches!(
+            super::lipsum_cow(21),
+            Cow::Borrowed(LIPSUM_OPENING)
+        ));
+        assert!(lipsum(30).starts_with(LIPSUM_OPENING));
+        for n in 1..25 {
+            assert_eq!(super::lipsum_cow(n), lipsum(n));
+        }
+    }
+
+    #[test]
+    fn small_rng() {
+        use rand::rngs::SmallRng;
+
+        let mut chain = MarkovChain::new();
+        chain.learn(LOREM_IPSUM);
+        let words = chain
+            .iter_with_rng(SmallRng::seed_from_u64(0))
+            .take(20)
+            .collect::<Vec<_>>();
+        let again = chain
+            .iter_with_rng(SmallRng::seed_from_u64(0))
+            .take(20)
+            .collect::<Vec<_>>();
+        assert_eq!(words, again);
+
+        let text = chain.generate_with_rng(SmallRng::seed_from_u64(0), 10);
+        assert!(text.ends_with('.'));
+    }
+
+    #[test]
+    fn learn_sentence() {
+        let mut chain = MarkovChain::new();
+        assert_eq!(chain.learn_sentence("a b c."), 2);
+        assert_eq!(chain.learn_sentence("x y z!"), 2);
+        assert_eq!(chain.learn_sentence("single"), 0);
+        assert_eq!(chain.starts, vec![("a", "b"), ("x", "y")]);
+        assert_eq!(chain.distinct_words(), 6);
+        assert_eq!(chain.estimate_sentences(7), 3.0);
+
+        for seed in 0..10 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = chain.generate_with_rng(rng, 9);
+            let words = text.split(' ').collect::<Vec<_>>();
+            for sentence in words.chunks(3) {
+                let sentence = sentence.join(" ");
+                assert!(
+                    sentence == "A b c." || sentence == "X y z!",
+                    "unexpected text: {:?}",
+                    text
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn comma_rate() {
+        let mut chain = MarkovChain::new();
+        chain.learn(LIBER_PRIMUS);
+        let count_commas = |rate| {
+            let rng = ChaCha20Rng::seed_from_u64(0);
+            let options = Options::new().comma_rate(rate);
+            let text = chain.generate_with_options(rng, 2000, &options);
+            assert!(!text.contains(",,"));
+            assert!(!text.contains(",."));
+            text.matches(',').count()
+        };
+
+        let baseline = count_commas(0.0);
+        let dense = count_commas(0.5);
+        assert!(dense > baseline + 500);
+        assert!(dense < baseline + 1000);
+    }
+
+    #[test]
+    fn try_from() {
+        assert_eq!(MarkovChain::try_from("").unwrap_err(), ChainError::Empty);
+        assert_eq!(MarkovChain::try_from("one").unwrap_err(), ChainError::Empty);
+        assert_eq!(
+            ChainError::Empty.to_string(),
+            "the Markov chain has no states"
+        );
+
+        let chain = MarkovChain::try_from("one two three").unwrap();
+        assert_eq!(chain.words(("one", "two")), Some(&vec!["three"]));
+    }
+
+    #[test]
+    fn lipsum_measured() {
+        for n in [0, 1, 10, 50, 200] {
+            let generated = super::lipsum_measured(n);
+            let words = generated.text.split_whitespace().collect::<Vec<_>>();
+            assert_eq!(generated.text, lipsum(n));
+            assert_eq!(generated.words, n);
+            assert_eq!(generated.words, words.len());
+            assert_eq!(generated.chars, generated.text.chars().count());
+            assert_eq!(
+                generated.sentences,
+                words.iter().filter(|w| w.ends_with(TERMINATORS)).count()
+            );
+        }
+    }
+
+    #[test]
+    fn learn_shuffled() {
+        let sentences = ["a b c d", "a b e f", "b c d g", "x y z"];
+        let mut ordered = MarkovChain::new();
+        for sentence in &sentences {
+            ordered.learn(sentence);
+        }
+
+        for seed in 0..5 {
+            let mut shuffled = MarkovChain::new();
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            assert_eq!(shuffled.learn_shuffled(&sentences, rng), ordered.len());
+            assert_eq!(shuffled.keys, ordered.keys);
+            assert_eq!(shuffled.fingerprint(), ordered.fingerprint());
+        }
+    }
+
+    #[test]
+    fn default_chain() {
+        let chain = super::default_chain();
+        assert_eq!(chain.generate_from(7, ("Lorem", "ipsum")), lipsum(7));
+        assert_eq!(chain.generate_from(100, ("Lorem", "ipsum")), lipsum(100));
+        LOREM_IPSUM_CHAIN.with(|default| {
+            assert_eq!(chain.fingerprint(), default.fingerprint());
+        });
+    }
+
+    #[test]
+    fn blocklist() {
+        let mut chain = MarkovChain::new();
+        chain.learn("we eat red apples and we eat bad apples and we eat green pears");
+        let blocklist = HashSet::from(["bad"]);
+        let options = Options::new().blocklist(&blocklist);
+
+        for seed in 0..20 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = chain.generate_with_options(rng, 200, &options);
+            assert!(
+                !text.to_lowercase().contains("bad"),
+                "found bad: {:?}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn blocklist_ignores_case_and_punctuation() {
+        let mut chain = MarkovChain::new();
+        chain.learn("Bad things happen. We eat bad, red apples. We eat good pears.");
+        chain.learn_sentence("Bad dogs bark");
+        chain.learn_sentence("good dogs sleep");
+        let blocklist = HashSet::from(["BAD"]);
+        let options = Options::new().blocklist(&blocklist);
+
+        for seed in 0..20 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = chain.generate_with_options(rng, 200, &options);
+            assert!(!text.to_lowercase().contains("bad"), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn blocklist_fallback() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c a b c");
+        let blocklist = HashSet::from(["c"]);
+        let options = Options::new().blocklist(&blocklist);
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(chain.generate_with_options(rng, 4, &options), "A b c a.");
+    }
+
+    #[test]
+    fn generate_to_writer() {
+        let mut chain = MarkovChain::new();
+        chain.learn(LIBER_PRIMUS);
+        for n in [0, 1, 10, 5000] {
+            let mut buf = Vec::new();
+            let rng = ChaCha20Rng::seed_from_u64(n as u64);
+            chain.generate_to_writer(rng, n, &mut buf).unwrap();
+            let rng = ChaCha20Rng::seed_from_u64(n as u64);
+            assert_eq!(
+                String::from_utf8(buf).unwrap(),
+                chain.generate_with_rng(rng, n)
+            );
+        }
+    }
+
+    #[test]
+    fn learn_paragraphs() {
+        let mut chain = MarkovChain::new();
+        chain.learn_paragraphs("\na b c d.\n  \n\ne f\ng h.\n\n");
+        assert_eq!(chain.words(("c", "d.")), Some(&vec![PARAGRAPH_BREAK]));
+        assert_eq!(chain.words(("d.", PARAGRAPH_BREAK)), Some(&vec!["e"]));
+        assert_eq!(chain.words(("f", "g")), Some(&vec!["h."]));
+        assert_eq!(chain.distinct_words(), 8);
+        assert_eq!(chain.estimate_sentences(8), 2.0);
+
+        let options = Options::new().paragraph_breaks(true);
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_options(rng, 20, &options);
+        assert!(text.contains(".\n\nE f"), "no paragraph break: {:?}", text);
+        assert_eq!(text.split_whitespace().count(), 20);
+
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_options(rng, 20, &Options::new());
+        assert!(!text.contains('\n'));
+        assert_eq!(text.split_whitespace().count(), 20);
+    }
+
+    #[test]
+    fn sample_bigram_weighted() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c a b d a b e a b f x y z");
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        let mut frequent = 0;
+        let mut rare = 0;
+        for _ in 0..1000 {
+            match chain.sample_bigram_weighted(&mut rng) {
+                Some(("a", "b")) => frequent += 1,
+                Some(("x", "y")) => rare += 1,
+                _ => {}
+            }
+        }
+        // The ("a", "b") bigram occurs four times, ("x", "y") once.
+        assert!(frequent > 2 * rare, "{} vs {}", frequent, rare);
+    }
+
+    #[test]
+    fn compound_rate() {
+        let mut chain = MarkovChain::new();
+        chain.learn(LIBER_PRIMUS);
+        let count_compounds = |rate| {
+            let rng = ChaCha20Rng::seed_from_u64(0);
+            let options = Options::new().compound_rate(rate);
+            let text = chain.generate_with_options(rng, 2000, &options);
+            text.split_whitespace().filter(|w| w.contains('-')).count()
+        };
+
+        let baseline = count_compounds(0.0);
+        let sparse = count_compounds(0.1);
+        let dense = count_compounds(0.5);
+        assert!(sparse > baseline + 50);
+        assert!(dense > sparse + 200);
+    }
+
+    #[test]
+    fn diff() {
+        let mut first = MarkovChain::new();
+        first.learn("a b c d e");
+        first.learn("x y z");
+        let mut second = MarkovChain::new();
+        second.learn("a b c d f");
+        second.learn("a b c");
+        second.learn("p q r");
+
+        let diff = first.diff(&second);
+        assert_eq!(diff.only_in_self, vec![("x", "y")]);
+        assert_eq!(diff.only_in_other, vec![("p", "q")]);
+        assert_eq!(diff.changed, vec![("a", "b"), ("c", "d")]);
+
+        let diff = second.diff(&first);
+        assert_eq!(diff.only_in_self, vec![("p", "q")]);
+        assert_eq!(diff.only_in_other, vec![("x", "y")]);
+
+        assert_eq!(first.diff(&first.clone()), ChainDiff::default());
+    }
+
+    #[test]
+    fn lipsum_keyed() {
+        assert_eq!(
+            super::lipsum_keyed("foo", 20),
+            super::lipsum_keyed("foo", 20)
+        );
+        assert_ne!(
+            super::lipsum_keyed("foo", 20),
+            super::lipsum_keyed("bar", 20)
+        );
+        // The seed is the FNV-1a hash of the key.
+        let rng = ChaCha20Rng::seed_from_u64(0xcbf2_9ce4_8422_2325);
+        assert_eq!(super::lipsum_keyed("", 20), lipsum_words_with_rng(rng, 20));
+        let rng = ChaCha20Rng::seed_from_u64(0xaf63_dc4c_8601_ec8c);
+        assert_eq!(super::lipsum_keyed("a", 20), lipsum_words_with_rng(rng, 20));
+    }
+
+    #[test]
+    fn sentence_starts() {
+        let mut chain = MarkovChain::new();
+        chain.learn(LIBER_PRIMUS);
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_rng(rng, 500);
+
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let words = chain.iter_with_rng(rng).sentence_starts().take(499);
+        for ((word, at_start), joined) in words.zip(text.split_whitespace()) {
+            if at_start {
+                assert_eq!(joined, capitalize(word));
+            } else {
+                assert_eq!(joined, word);
+            }
+        }
+    }
+
+    #[test]
+    fn capacity() {
+        let mut chain = MarkovChain::new();
+        assert_eq!(chain.capacity(), 0);
+
+        chain.learn(LOREM_IPSUM);
+        assert!(chain.capacity() >= chain.len());
+        chain.learn(LIBER_PRIMUS);
+        assert!(chain.capacity() >= chain.len());
+    }
+
+    #[test]
+    fn em_dash_rate() {
+        let mut chain = MarkovChain::new();
+        chain.learn(LIBER_PRIMUS);
+        let em_dashes = |rate| {
+            let rng = ChaCha20Rng::seed_from_u64(0);
+            let options = Options::new().em_dash_rate(rate);
+            let text = chain.generate_with_options(rng, 2000, &options);
+            let words = text.split(' ').collect::<Vec<_>>();
+            for (i, _) in words.iter().enumerate().filter(|(_, &w)| w == "—") {
+                assert!(!words[i - 1].ends_with(is_ascii_punctuation));
+                assert!(!words[i + 1].starts_with(is_ascii_punctuation));
+                // The final '.' is added after the last word.
+                assert!(i + 2 == words.len() || !words[i + 1].ends_with(TERMINATORS));
+            }
+            text.matches('—').count()
+        };
+
+        assert_eq!(em_dashes(0.0), 0);
+        let sparse = em_dashes(0.1);
+        let dense = em_dashes(0.5);
+        assert!(sparse > 50);
+        assert!(dense > sparse + 200);
+    }
+
+    #[test]
+    fn lipsum_dropcap() {
+        for n in 1..30 {
+            let (first, rest) = super::lipsum_dropcap(n);
+            assert_eq!(format!("{}{}", first, rest), lipsum(n));
+            assert!(first.is_uppercase());
+        }
+        assert_eq!(super::lipsum_dropcap(1), ('L', String::from("orem.")));
+        assert_eq!(super::lipsum_dropcap(0), (' ', String::new()));
+    }
+
+    #[test]
+    fn chain_sequence() {
+        let mut first = MarkovChain::new();
+        first.learn("a b c. d e f! a b d. e f c.");
+        let mut second = MarkovChain::new();
+        second.learn("u v w. x y z? u v x. y z w.");
+        let empty = MarkovChain::new();
+        let sequence = ChainSequence::new()
+            .then(&first, 3)
+            .then(&empty, 2)
+            .then(&second, 4);
+
+        for seed in 0..10 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = sequence.generate_with_rng(rng).to_lowercase();
+            let sentences = text
+                .split_inclusive(TERMINATORS)
+                .map(str::trim)
+                .collect::<Vec<_>>();
+            assert_eq!(sentences.len(), 7, "{:?}", text);
+            for sentence in &sentences[..3] {
+                assert!(sentence.chars().all(|c| !"uvwxyz".contains(c)));
+                assert!(["a b", "d e", "e f"]
+                    .iter()
+                    .any(|s| sentence.starts_with(s)));
+            }
+            for sentence in &sentences[3..] {
+                assert!(sentence.chars().all(|c| !"abcdef".contains(c)));
+                assert!(["u v", "x y", "y z"]
+                    .iter()
+                    .any(|s| sentence.starts_with(s)));
+            }
+        }
+    }
+
+    #[test]
+    fn validate() {
+        let mut chain = MarkovChain::new();
+        assert_eq!(chain.validate(), Ok(()));
+        chain.learn(LOREM_IPSUM);
+        assert_eq!(chain.validate(), Ok(()));
+
+        let mut unsorted = chain.clone();
+        unsorted.keys.swap(0, 1);
+        assert_eq!(unsorted.validate(), Err(ChainError::UnsortedKeys));
+
+        let mut missing = chain.clone();
+        missing.keys.pop();
+        assert_eq!(missing.validate(), Err(ChainError::InconsistentKeys));
+
+        let mut unknown = chain.clone();
+        unknown.keys.push(("zzz", "zzz"));
+        unknown.keys.remove(0);
+        assert_eq!(unknown.validate(), Err(ChainError::InconsistentKeys));
+
+        let mut empty = chain.clone();
+        empty.map.get_mut(&("Lorem", "ipsum")).unwrap().clear();
+        assert_eq!(empty.validate(), Err(ChainError::EmptySuccessors));
+    }
+
+    #[test]
+    fn punctuation_style() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b? c d! e: f; g h;");
+        let french = Options::new().punctuation_style(PunctuationStyle::French);
+        assert_eq!(
+            chain.generate_with_options(ChaCha20Rng::seed_from_u64(0), 7, &french),
+            "D\u{202f}! E\u{202f}: f\u{202f}; g g b\u{202f}? C."
+        );
+        assert_eq!(
+            chain.generate_with_options(ChaCha20Rng::seed_from_u64(0), 7, &Options::new()),
+            "D! E: f; g g b? C."
+        );
+        // The space is removed together with the punctuation when a
+        // sentence is ended.
+        assert_eq!(join_words(["a", "b;"].into_iter(), &french), "A b.");
+    }
+
+    #[test]
+    fn lipsum_bytes() {
+        assert_eq!(super::lipsum_bytes(0), b"");
+        for n in (1..200).chain([1000, 10_000]) {
+            let bytes = super::lipsum_bytes(n);
+            assert!(String::from_utf8(bytes.clone()).is_ok());
+            assert!(bytes.len() <= n);
+            assert!(bytes.len() + 3 >= n);
+        }
+    }
+
+    #[test]
+    fn lipsum_toc() {
+        assert_eq!(super::lipsum_toc(5, 0), vec![]);
+        assert_eq!(super::lipsum_toc(0, 3), vec![]);
+        assert!(super::lipsum_toc(10, 1)
+            .iter()
+            .all(|&(level, _)| level == 1));
+
+        let toc = super::lipsum_toc_with_rng(ChaCha20Rng::seed_from_u64(0), 100, 4);
+        assert_eq!(toc.len(), 100);
+        assert_eq!(toc[0].0, 1);
+        for pair in toc.windows(2) {
+            assert!(pair[1].0 <= pair[0].0 + 1);
+        }
+        assert!(toc
+            .iter()
+            .all(|(level, title)| (1..=4).contains(level) && !title.is_empty()));
+        assert!(toc.iter().any(|&(level, _)| level == 4));
+    }
+
+    #[test]
+    fn terminate() {
+        let options = Options::new().terminate(false);
+        assert_eq!(join_words(["a", "b,"].into_iter(), &options), "A b,");
+        assert_eq!(join_words(["a", "b."].into_iter(), &options), "A b.");
+        assert_eq!(join_words(["a.", "b"].into_iter(), &options), "A. B");
+
+        let options = options.max_sentence_chars(5);
+        assert_eq!(
+            join_words(["aa", "bb,", "cc", "dd"].into_iter(), &options),
+            "Aa. Bb. Cc. Dd"
+        );
+    }
+
+    #[test]
+    fn sentence_start_bigrams() {
+        let mut chain = MarkovChain::new();
+        assert_eq!(chain.sentence_starts(), vec![]);
+
+        chain.learn("a b c. d e f! g h");
+        assert_eq!(chain.sentence_starts(), vec![("a", "b"), ("d", "e")]);
+
+        chain.learn("x y z");
+        chain.learn_sentence("p q r.");
+        chain.learn_paragraphs("s t u\n\nv w x");
+        assert_eq!(
+            chain.sentence_starts(),
+            vec![
+                ("a", "b"),
+                ("d", "e"),
+                ("p", "q"),
+                ("s", "t"),
+                ("v", "w"),
+                ("x", "y")
+            ]
+        );
+    }
+
+    #[test]
+    fn generate_with_refrain() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c. d e f. g h i. a b d.");
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_refrain(rng, 7, "la la la,", 3);
+        let sentences = text.split_inclusive('.').map(str::trim).collect::<Vec<_>>();
+        assert_eq!(sentences.len(), 9, "{:?}", text);
+        for (i, sentence) in sentences.iter().enumerate() {
+            assert_eq!(*sentence == "La la la.", i == 3 || i == 7, "{:?}", text);
+        }
+
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_refrain(rng, 3, "refrain", 0);
+        assert!(!text.contains("Refrain"));
+    }
+
+    #[test]
+    fn transitions_round_trip() {
+        let mut chain = MarkovChain::new();
+        chain.learn(LOREM_IPSUM);
+        chain.learn("foo bar baz foo bar baz foo bar qux");
+        let text = chain.to_transitions_string();
+        let parsed = MarkovChain::from_transitions_str(&text).unwrap();
+        assert_eq!(parsed.fingerprint(), chain.fingerprint());
+        assert_eq!(parsed.keys, chain.keys);
+        assert_eq!(parsed.validate(), Ok(()));
+        assert_eq!(parsed.to_transitions_string(), text);
+    }
+
+    #[test]
+    fn from_transitions_str_errors() {
+        assert_eq!(
+            MarkovChain::from_transitions_str("a b -> c\n\na b c d\n").unwrap_err(),
+            ChainError::Parse { line: 3 }
+        );
+        assert_eq!(
+            MarkovChain::from_transitions_str("a b ->").unwrap_err(),
+            ChainError::Parse { line: 1 }
+        );
+        assert_eq!(
+            ChainError::Parse { line: 7 }.to_string(),
+            "invalid transition on line 7"
+        );
+        assert!(MarkovChain::from_transitions_str("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn generate_interleaved_blends_vocabularies() {
+        let mut latin = MarkovChain::new();
+        latin.learn("lorem ipsum dolor sit amet consectetur adipiscing elit");
+        let mut english = MarkovChain::new();
+        english.learn("the quick brown fox jumps over the lazy dog");
+        let rng = ChaCha20Rng::seed_from_u64(5);
+        let text = latin.generate_interleaved(&english, rng, 40).to_lowercase();
+        let words = text
+            .trim_end_matches('.')
+            .split(' ')
+            .collect::<HashSet<_>>();
+        let knows = |chain: &MarkovChain, word: &str| chain.keys.iter().any(|key| key.0 == word);
+        assert!(words.iter().any(|word| knows(&latin, word)));
+        assert!(words.iter().any(|word| knows(&english, word)));
+    }
+
+    #[test]
+    fn generate_interleaved_empty_chain() {
+        let mut chain = MarkovChain::new();
+        chain.learn("red orange yellow");
+        let empty = MarkovChain::new();
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(chain.generate_interleaved(&empty, rng, 0), "");
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(
+            empty
+                .generate_interleaved(&chain, rng, 3)
+                .split(' ')
+                .count(),
+            3
+        );
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(empty.generate_interleaved(&empty, rng, 3), "");
+    }
+
+    #[test]
+    fn generate_abecedarian_initials_are_sorted() {
+        let mut chain = MarkovChain::new();
+        chain.learn(LOREM_IPSUM);
+        let rng = ChaCha20Rng::seed_from_u64(2);
+        let text = chain.generate_abecedarian(rng);
+
+        let mut initials = Vec::new();
+        let mut at_start = true;
+        for word in text.split(' ') {
+            if at_start {
+                initials.push(word.chars().next().unwrap().to_ascii_lowercase());
+            }
+            at_start = word.ends_with(TERMINATORS);
+        }
+        assert!(initials.len() > 3);
+        assert!(initials.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        assert_eq!(
+            MarkovChain::new().generate_abecedarian(ChaCha20Rng::seed_from_u64(0)),
+            ""
+        );
+    }
+
+    #[test]
+    fn lipsum_classic_paragraphs_with_max_words() {
+        for max_words in [0, 5, 19, 20, 100, 250] {
+            let rng = ChaCha20Rng::seed_from_u64(max_words as u64);
+            let text = super::lipsum_classic_paragraphs_with_max_words(rng, 5, max_words);
+            assert!(text.split_whitespace().count() <= max_words);
+            for paragraph in text.split("\n\n") {
+                assert!(paragraph.is_empty() || paragraph.ends_with(TERMINATORS));
+            }
+        }
+
+        // The opening sentence has 19 words.
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(
+            super::lipsum_classic_paragraphs_with_max_words(rng, 5, 18),
+            ""
+        );
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(
+            super::lipsum_classic_paragraphs_with_max_words(rng, 5, 19),
+            CLASSIC_OPENING
+        );
+
+        // A large cap gives the same text as no cap.
+        let rng = ChaCha20Rng::seed_from_u64(3);
+        let text = super::lipsum_classic_paragraphs_with_max_words(rng, 3, usize::MAX);
+        let rng = ChaCha20Rng::seed_from_u64(3);
+        assert_eq!(text, lipsum_classic_paragraphs_with_rng(rng, 3));
+    }
+
+    #[test]
+    fn generate_checked() {
+        let mut chain = MarkovChain::new();
+        chain.learn(LOREM_IPSUM);
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let error = chain.generate_checked(rng, 20).unwrap_err();
+        assert_eq!(error, LowDiversityError { diversity: 0.0 });
+        assert_eq!(
+            error.to_string(),
+            "the Markov chain is too repetitive (diversity 0)"
+        );
+
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert!(MarkovChain::new().generate_checked(rng, 20).is_err());
+
+        chain.learn(LIBER_PRIMUS);
+        assert!(chain.diversity() >= MIN_DIVERSITY);
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_checked(rng, 20).unwrap();
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(text, chain.generate_with_rng(rng, 20));
+    }
+
+    #[test]
+    fn generate_proper_start() {
+        let mut chain = MarkovChain::new();
+        chain.learn(LIBER_PRIMUS);
+        for seed in 0..20 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = chain.generate_proper_start(rng, 10);
+            let first = text.split(' ').next().unwrap();
+            assert!(first.starts_with(char::is_uppercase));
+            assert!(chain.keys.iter().any(|&(word, _)| word == first));
+        }
+
+        let mut chain = MarkovChain::new();
+        chain.learn("all lowercase words here");
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(chain.generate_proper_start(rng, 2).split(' ').count(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "ssml")]
+    fn lipsum_ssml() {
+        assert_eq!(super::lipsum_ssml(0), "<speak></speak>");
+
+        let rng = ChaCha20Rng::seed_from_u64(4);
+        let ssml = lipsum_ssml_with_rng(rng, 5);
+        let body = ssml
+            .strip_prefix("<speak>")
+            .and_then(|ssml| ssml.strip_suffix("</speak>"))
+            .unwrap();
+        let sentences = body.split(SSML_BREAK).collect::<Vec<_>>();
+        assert_eq!(sentences.len(), 5);
+        for sentence in sentences {
+            let text = sentence
+                .strip_prefix("<s>")
+                .and_then(|s| s.strip_suffix("</s>"))
+                .unwrap();
+            assert!(!text.contains(['<', '>']));
+            assert!(text.ends_with(TERMINATORS));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "ssml")]
+    fn push_xml_escaped() {
+        let mut out = String::from("<s>");
+        super::push_xml_escaped(&mut out, "a < b & \"c\" > 'd'");
+        assert_eq!(out, "<s>a &lt; b &amp; &quot;c&quot; &gt; &apos;d&apos;");
+    }
+
+    #[test]
+    fn chain_cache_learns_once_per_key() {
+        let calls = std::cell::Cell::new(0);
+        let corpus = |text| {
+            calls.set(calls.get() + 1);
+            text
+        };
+
+        let cache = ChainCache::new();
+        let first = cache.get_or_learn(1, || corpus(LOREM_IPSUM));
+        let second = cache.get_or_learn(1, || corpus(LOREM_IPSUM));
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(calls.get(), 1);
+
+        let other = cache.get_or_learn(2, || corpus(LIBER_PRIMUS));
+        assert!(!Arc::ptr_eq(&first, &other));
+        cache.get_or_learn(2, || corpus(LIBER_PRIMUS));
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn chain_cache_is_sync() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<ChainCache<'static, String>>();
+    }
+
+    #[test]
+    fn max_consecutive_short() {
+        // Every pair of short words is followed by both short and
+        // long words somewhere in the corpus.
+        let short = ["et", "ut", "in", "ad"];
+        let mut corpus = String::from("et ut in ad et in ut ad ut et ad in");
+        for (i, a) in short.iter().enumerate() {
+            for b in &short {
+                corpus.push_str(&format!(" {} {} magnificus{}", a, b, i));
+            }
+        }
+        // Avoid a dead end, random jumps can create long runs.
+        corpus.push_str(" et ut in ad");
+        let mut chain = MarkovChain::new();
+        chain.learn(&corpus);
+        let max_run = |text: &str| {
+            let mut run = 0;
+            let mut max = 0;
+            for word in text.split(' ') {
+                run = if super::is_short_word(word) {
+                    run + 1
+                } else {
+                    0
+                };
+                max = max.max(run);
+            }
+            max
+        };
+
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert!(max_run(&chain.generate_with_rng(rng, 100)) > 2);
+        for max_short in 2..=3 {
+            let options = Options::new().max_consecutive_short(max_short);
+            for seed in 0..10 {
+                let rng = ChaCha20Rng::seed_from_u64(seed);
+                let text = chain.generate_with_options(rng, 100, &options);
+                // The run might be longer at the start of the text.
+                let text = text.splitn(4, ' ').last().unwrap();
+                assert!(max_run(text) <= max_short, "{}: {}", max_short, text);
+            }
+        }
+    }
+
+    #[test]
+    fn is_short_word() {
+        assert!(super::is_short_word("et"));
+        assert!(super::is_short_word("sed,"));
+        assert!(!super::is_short_word("dolor"));
+        assert!(!super::is_short_word(SENTENCE_END));
+        assert!(!super::is_short_word(PARAGRAPH_BREAK));
+        assert!(!super::is_short_word("..."));
+    }
+
+    #[test]
+    fn lorem_ipsum_sentences() {
+        let sentences = super::lorem_ipsum_sentences();
+        assert_eq!(sentences.len(), 4);
+        assert!(sentences[0].starts_with("Lorem ipsum"));
+        assert!(sentences[3].ends_with("id est laborum."));
+        for sentence in &sentences {
+            assert_eq!(sentence.trim(), *sentence);
+            assert!(sentence.ends_with(TERMINATORS));
+        }
+        assert_eq!(
+            sentences.join(" ").split_whitespace().collect::<Vec<_>>(),
+            LOREM_IPSUM.split_whitespace().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn no_repeat_window() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b a b a c d a b e a b a f b a g a b h b a i");
+        let repeats = |text: &str, window: usize| {
+            let words = text.trim_end_matches('.').split(' ').collect::<Vec<_>>();
+            (window..words.len())
+                .filter(|&i| words[i - window..i].contains(&words[i]))
+                .count()
+        };
+
+        let mut plain = 0;
+        let mut windowed = 0;
+        for seed in 0..10 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            plain += repeats(&chain.generate_with_rng(rng, 50).to_lowercase(), 2);
+            let options = Options::new().no_repeat_window(2);
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = chain.generate_with_options(rng, 50, &options);
+            windowed += repeats(&text.to_lowercase(), 2);
+        }
+        assert!(windowed < plain, "{} >= {}", windowed, plain);
+
+        // The window does not change the output by default.
+        let options = Options::new().no_repeat_window(0);
+        let rng = ChaCha20Rng::seed_from_u64(1);
+        let text = chain.generate_with_options(rng, 50, &options);
+        let rng = ChaCha20Rng::seed_from_u64(1);
+        assert_eq!(text, chain.generate_with_rng(rng, 50));
+    }
+
+    #[test]
+    fn chain_eq_and_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |chain: &MarkovChain| {
+            let mut hasher = DefaultHasher::new();
+            chain.hash(&mut hasher);
+            hasher.finish()
         };
-        self.iter_with_rng_from(rng, initial_bigram)
+
+        let mut first = MarkovChain::new();
+        first.learn("a b c a b d");
+        first.learn("x y z");
+        // The same transitions learned in other pieces, which gives
+        // other starts and word counts.
+        let mut second = MarkovChain::new();
+        second.learn("x y z");
+        second.learn("c a b d");
+        second.learn("a b c a");
+        assert_ne!(first.sentence_starts(), second.sentence_starts());
+        assert_eq!(first, second);
+        assert_eq!(hash(&first), hash(&second));
+        let third = second.clone();
+
+        // The number of times a transition was learned matters.
+        second.learn("a b c");
+        assert_ne!(first, second);
+        assert_ne!(hash(&first), hash(&second));
+        assert_ne!(first, MarkovChain::new());
+
+        let mut cache = HashMap::new();
+        cache.insert(first.clone(), "first");
+        assert_eq!(cache.get(&third), Some(&"first"));
     }
 
-    /// Make a never-ending iterator over the words in the Markov chain. The
-    /// iterator starts at a predetermined point in the chain.
-    pub fn iter(&self) -> Words<'_, impl Rng> {
-        self.iter_with_rng(default_rng())
+    #[test]
+    fn chain_eq_consistent_with_fingerprint() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |chain: &MarkovChain| {
+            let mut hasher = DefaultHasher::new();
+            chain.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        // Same transitions, but different sentence starts.
+        let mut first = MarkovChain::new();
+        first.learn_sentence("a b c");
+        let mut second = MarkovChain::new();
+        second.learn("a b c");
+        second.learn_sentence("b c");
+        assert_ne!(first, second);
+        assert_ne!(first.fingerprint(), second.fingerprint());
+        assert_ne!(hash(&first), hash(&second));
+
+        let mut third = MarkovChain::new();
+        third.learn_sentence("a b c");
+        assert_eq!(first, third);
+        assert_eq!(first.fingerprint(), third.fingerprint());
+        assert_eq!(hash(&first), hash(&third));
     }
 
-    /// Make a never-ending iterator over the words in the Markov
-    /// chain. The iterator starts at the given bigram.
-    pub fn iter_with_rng_from<R: Rng>(&self, rng: R, from: Bigram<'a>) -> Words<'_, R> {
-        Words {
-            map: &self.map,
-            rng,
-            keys: &self.keys,
-            state: from,
+    #[test]
+    fn lipsum_grid() {
+        let grid = super::lipsum_grid(4, 3);
+        assert_eq!(grid.len(), 4);
+        for row in &grid {
+            assert_eq!(row.len(), 3);
+            assert!(row.iter().all(|cell| !cell.is_empty()));
         }
-    }
+        assert_ne!(grid[0][0], grid[0][1]);
 
-    /// Make a never-ending iterator over the words in the Markov
-    /// chain. The iterator starts at the given bigram.
-    pub fn iter_from(&self, from: Bigram<'a>) -> Words<'_, impl Rng> {
-        self.iter_with_rng_from(default_rng(), from)
+        assert!(super::lipsum_grid(0, 3).is_empty());
+        assert_eq!(super::lipsum_grid(2, 0), vec![Vec::<String>::new(); 2]);
     }
-}
-
-/// Provide a default random number generator. This generator is seeded and will
-/// always produce the same sequence of numbers. The seed is chosen to yield
-/// good results for the included Markov chain.
-fn default_rng() -> impl Rng {
-    ChaCha20Rng::seed_from_u64(97)
-}
 
-/// Never-ending iterator over words in the Markov chain.
-///
-/// Generated with the [`iter`] or [`iter_from`] methods.
-///
-/// [`iter`]: struct.MarkovChain.html#method.iter
-/// [`iter_from`]: struct.MarkovChain.html#method.iter_from
-pub struct Words<'a, R: Rng> {
-    map: &'a HashMap<Bigram<'a>, Vec<&'a str>>,
-    rng: R,
-    keys: &'a Vec<Bigram<'a>>,
-    state: Bigram<'a>,
-}
+    #[test]
+    fn number_policy() {
+        let mut chain = MarkovChain::new();
+        chain.learn("Genesis 1:1 and psalm 23, verse 4. Then 12 more in 1984 and 3 more.");
+        let has_digits = |text: &str| text.contains(|c: char| c.is_ascii_digit());
 
-impl<'a, R: Rng> Iterator for Words<'a, R> {
-    type Item = &'a str;
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert!(has_digits(&chain.generate_with_rng(rng, 50)));
 
-    fn next(&mut self) -> Option<&'a str> {
-        if self.map.is_empty() {
-            return None;
+        let options = Options::new().numbers(NumberPolicy::Strip);
+        for seed in 0..5 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = chain.generate_with_options(rng, 50, &options);
+            assert!(!has_digits(&text));
+            assert_eq!(text.split_whitespace().count(), 50);
         }
 
-        let result = Some(self.state.0);
+        let options = Options::new().numbers(NumberPolicy::Replace(5..5));
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_options(rng, 50, &options);
+        assert!(!has_digits(&text));
+        assert_eq!(text.split_whitespace().count(), 50);
 
-        while !self.map.contains_key(&self.state) {
-            self.state = *self.keys.choose(&mut self.rng).unwrap();
+        let options = Options::new().numbers(NumberPolicy::Replace(100..1000));
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_options(rng, 50, &options);
+        let numbers = text
+            .split(' ')
+            .filter(|word| super::is_number(word))
+            .collect::<Vec<_>>();
+        assert!(!numbers.is_empty());
+        for number in numbers {
+            let digits = number.trim_matches(is_ascii_punctuation);
+            assert!(
+                (100..1000).contains(&digits.parse::<u64>().unwrap()),
+                "{}",
+                number
+            );
         }
-        let next_words = &self.map[&self.state];
-        let next = next_words.choose(&mut self.rng).unwrap();
-        self.state = (self.state.1, next);
-        result
-    }
-}
 
-/// Check if `c` is an ASCII punctuation character.
-fn is_ascii_punctuation(c: char) -> bool {
-    c.is_ascii_punctuation()
-}
+        // Only numbers would be generated.
+        let mut chain = MarkovChain::new();
+        chain.learn("1 2 3 4 5");
+        let options = Options::new().numbers(NumberPolicy::Strip);
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(chain.generate_with_options(rng, 10, &options), "");
+    }
 
-/// Capitalize the first character in a string.
-fn capitalize(word: &str) -> String {
-    let idx = match word.chars().next() {
-        Some(c) => c.len_utf8(),
-        None => 0,
-    };
+    #[test]
+    fn is_number() {
+        assert!(super::is_number("1984"));
+        assert!(super::is_number("3:16"));
+        assert!(super::is_number("(12),"));
+        assert!(!super::is_number("..."));
+        assert!(!super::is_number("4th"));
+    }
 
-    let mut result = String::with_capacity(word.len());
-    result.push_str(&word[..idx].to_uppercase());
-    result.push_str(&word[idx..]);
-    result
-}
+    #[test]
+    fn generate_ending_with_question() {
+        LOREM_IPSUM_CHAIN.with(|chain| {
+            for seed in 0..20 {
+                let rng = ChaCha20Rng::seed_from_u64(seed);
+                let text = chain.generate_ending_with_question(rng, 15);
+                assert!(text.ends_with('?'));
+                assert!(!text.ends_with(".?") && !text.ends_with(",?"));
 
-/// Join words from an iterator. The first word is always capitalized
-/// and the generated sentence will end with `'.'` if it doesn't
-/// already end with some other ASCII punctuation character.
-fn join_words<'a, I: Iterator<Item = &'a str>>(mut words: I) -> String {
-    match words.next() {
-        None => String::new(),
-        Some(word) => {
-            // Punctuation characters which ends a sentence.
-            let punctuation: &[char] = &['.', '!', '?'];
+                // Only the final terminator is changed.
+                let rng = ChaCha20Rng::seed_from_u64(seed);
+                let plain = chain.generate_with_rng(rng, 15);
+                assert_eq!(text[..text.len() - 1], plain[..text.len() - 1]);
+            }
+        });
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(MarkovChain::new().generate_ending_with_question(rng, 5), "");
+    }
 
-            let mut sentence = capitalize(word);
-            let mut needs_cap = sentence.ends_with(punctuation);
+    #[test]
+    fn most_common_bigram_ties() {
+        let mut chain = MarkovChain::new();
+        chain.learn("d e f d e g b c x b c y");
+        // Both ("b", "c") and ("d", "e") occur twice.
+        assert_eq!(chain.most_common_bigram(), Some(("b", "c")));
+        chain.learn("d e z");
+        assert_eq!(chain.most_common_bigram(), Some(("d", "e")));
+    }
 
-            // Add remaining words.
-            for word in words {
-                sentence.push(' ');
+    #[test]
+    fn generate_readability() {
+        let average_word_length = |text: &str| {
+            let words = text.split_whitespace().collect::<Vec<_>>();
+            let chars = words
+                .iter()
+                .map(|word| word.trim_matches(is_ascii_punctuation).chars().count())
+                .sum::<usize>();
+            chars as f64 / words.len() as f64
+        };
 
-                if needs_cap {
-                    sentence.push_str(&capitalize(word));
-                } else {
-                    sentence.push_str(word);
-                }
+        LOREM_IPSUM_CHAIN.with(|chain| {
+            let rng = ChaCha20Rng::seed_from_u64(0);
+            let easy = chain.generate_readability(rng, 2000, Readability::Easy);
+            let rng = ChaCha20Rng::seed_from_u64(0);
+            let hard = chain.generate_readability(rng, 2000, Readability::Hard);
+            assert_eq!(easy.split_whitespace().count(), 2000);
+            assert!(average_word_length(&easy) < average_word_length(&hard));
+            assert!(easy.matches('.').count() > hard.matches('.').count());
+        });
+    }
 
-                needs_cap = word.ends_with(punctuation);
+    #[test]
+    fn lipsum_document_headings_in_order() {
+        let headings = ["Terms of Service", "Privacy", "Cookies", "Contact"];
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let document = lipsum_document_with_rng(rng, &headings, 3);
+        let blocks = document.split("\n\n").collect::<Vec<_>>();
+        assert_eq!(blocks.len(), 4 * (1 + 3));
+        for (i, heading) in headings.iter().enumerate() {
+            assert_eq!(blocks[i * 4], *heading);
+            for paragraph in &blocks[i * 4 + 1..i * 4 + 4] {
+                assert!(paragraph.ends_with(TERMINATORS));
             }
+        }
+
+        assert_eq!(super::lipsum_document(&[], 3), "");
+        assert_eq!(super::lipsum_document(&["A", "B"], 0), "A\n\nB");
+    }
+
+    #[test]
+    fn lipsum_seeded_salted() {
+        let text = super::lipsum_seeded_salted(7, "alpha", 30);
+        assert_eq!(text, super::lipsum_seeded_salted(7, "alpha", 30));
+        assert_ne!(text, super::lipsum_seeded_salted(7, "beta", 30));
+        assert_ne!(text, super::lipsum_seeded_salted(8, "alpha", 30));
+        // The salt is not simply appended to the key.
+        assert_ne!(text, super::lipsum_keyed("alpha", 30));
+
+        let mut hasher = Fnv1a::new();
+        hasher.write(&7u64.to_le_bytes());
+        hasher.write(b"alpha");
+        let rng = ChaCha20Rng::seed_from_u64(hasher.finish());
+        assert_eq!(text, lipsum_words_with_rng(rng, 30));
+    }
+
+    #[test]
+    fn reachable_from() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c a b d a c b a b");
+        chain.learn(LOREM_IPSUM);
+        let trimmed = chain.reachable_from(("a", "b"));
+        assert_eq!(trimmed.validate(), Ok(()));
+        assert!(trimmed.len() < chain.len());
+        assert!(trimmed.keys.iter().all(|state| state.0.len() == 1));
+
+        for seed in 0..10 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let expected = chain.generate_with_rng_from(rng, 100, ("a", "b"));
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            assert_eq!(
+                trimmed.generate_with_rng_from(rng, 100, ("a", "b")),
+                expected
+            );
+        }
+
+        assert!(chain.reachable_from(("foo", "bar")).is_empty());
+    }
 
-            // Ensure the sentence ends with either one of ".!?".
-            if !sentence.ends_with(punctuation) {
-                // Trim all trailing punctuation characters to avoid
-                // adding '.' after a ',' or similar.
-                let idx = sentence.trim_end_matches(is_ascii_punctuation).len();
-                sentence.truncate(idx);
-                sentence.push('.');
+    #[test]
+    fn truncate_long_words() {
+        let options = Options::new().truncate_long_words(4);
+        LOREM_IPSUM_CHAIN.with(|chain| {
+            let rng = ChaCha20Rng::seed_from_u64(0);
+            let text = chain.generate_with_options(rng, 500, &options);
+            assert_eq!(text.split(' ').count(), 500);
+            for word in text.split(' ') {
+                let letters = word.trim_end_matches(is_ascii_punctuation);
+                assert!(letters.chars().count() <= 4, "{}", word);
             }
+        });
+    }
+
+    #[test]
+    fn truncate_word() {
+        assert_eq!(super::truncate_word("lorem", 5), "lorem");
+        assert_eq!(super::truncate_word("lorem,", 3), "lor,");
+        assert_eq!(super::truncate_word("søster.", 2), "sø.");
+        assert_eq!(super::truncate_word("ipsum", 0), "i");
+        assert_eq!(super::truncate_word("...", 1), "...");
+    }
 
-            sentence
+    #[test]
+    fn lipsum_vertical() {
+        assert_eq!(super::lipsum_vertical(0), "");
+        for n in [1, 10, 200] {
+            let rng = ChaCha20Rng::seed_from_u64(n as u64);
+            let text = lipsum_vertical_with_rng(rng, n);
+            assert_eq!(text.lines().count(), n);
+            assert!(text.starts_with("Lorem"));
+            assert!(text
+                .lines()
+                .all(|line| !line.contains(is_ascii_punctuation)));
+            assert!(!text.contains(' '));
         }
     }
-}
 
-/// The traditional lorem ipsum text as given in [Wikipedia]. Using
-/// this text alone for a Markov chain of order two doesn't work very
-/// well since each bigram (two consequtive words) is followed by just
-/// one other word. In other words, the Markov chain will always
-/// produce the same output and recreate the lorem ipsum text
-/// precisely. However, combining it with the full text in
-/// [`LIBER_PRIMUS`] works well.
-///
-/// [Wikipedia]: https://en.wikipedia.org/wiki/Lorem_ipsum
-/// [`LIBER_PRIMUS`]: constant.LIBER_PRIMUS.html
-pub const LOREM_IPSUM: &str = include_str!("lorem-ipsum.txt");
+    #[test]
+    fn generate_with_selector() {
+        struct First;
+        impl Selector for First {
+            fn choose<'a>(&mut self, candidates: &[(&'a str, u32)], _: &mut impl Rng) -> &'a str {
+                candidates[0].0
+            }
+        }
 
-/// The first book in Cicero's work De finibus bonorum et malorum ("On
-/// the ends of good and evil"). The lorem ipsum text in
-/// [`LOREM_IPSUM`] is derived from part of this text.
-///
-/// [`LOREM_IPSUM`]: constant.LOREM_IPSUM.html
-pub const LIBER_PRIMUS: &str = include_str!("liber-primus.txt");
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c a b d a b e a b c a b");
+        for seed in 0..5 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = chain.generate_with_selector(rng, 30, First);
+            // Only the random start can contain other words.
+            let rest = text.splitn(4, ' ').last().unwrap();
+            assert!(rest.contains("a b c a b c a b c"));
+            assert!(!rest.contains('d') && !rest.contains('e'));
+        }
 
-thread_local! {
-    // Markov chain generating lorem ipsum text.
-    static LOREM_IPSUM_CHAIN: MarkovChain<'static> = {
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_selector(rng, 100, FrequencySelector);
+        assert!(text.contains('d'));
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(MarkovChain::new().generate_with_selector(rng, 5, First), "");
+    }
+
+    #[test]
+    fn count_words() {
+        assert_eq!(
+            super::count_words(&["b", "a", "b", "c", "b"]),
+            vec![("b", 3), ("a", 1), ("c", 1)]
+        );
+    }
+
+    #[test]
+    fn temperature() {
         let mut chain = MarkovChain::new();
-        // The cost of learning increases as more and more text is
-        // added, so we start with the smallest text.
-        chain.learn(LOREM_IPSUM);
-        chain.learn(LIBER_PRIMUS);
-        chain
+        chain.learn("a b c a b c a b c a b c a b c a b c a b d a b e a b f a b");
+        // Count how often a rare successor is selected.
+        let rare = |temperature: f64| {
+            let options = Options::new().temperature(temperature);
+            let rng = ChaCha20Rng::seed_from_u64(0);
+            let text = chain.generate_with_options(rng, 3000, &options);
+            text.split(' ')
+                .filter(|word| ["d", "e", "f"].contains(word))
+                .count()
+        };
+        assert_eq!(rare(0.0), 0);
+        assert_eq!(rare(-1.0), 0);
+        assert!(rare(0.2) < rare(1.0));
+        assert!(rare(1.0) < rare(10.0));
     }
-}
 
-/// Generate `n` words of lorem ipsum text. The output will always start with
-/// "Lorem ipsum".
-///
-/// The text continues with the standard lorem ipsum text from [`LOREM_IPSUM`]
-/// and becomes randomly generated but deterministic if more than 18 words is
-/// requested. See [`lipsum_words`] if fully random text is needed.
-///
-/// # Examples
-///
-/// ```
-/// use lipsum::lipsum;
-///
-/// assert_eq!(lipsum(7), "Lorem ipsum dolor sit amet, consectetur adipiscing.");
-/// ```
-///
-/// [`LOREM_IPSUM`]: constant.LOREM_IPSUM.html
-/// [`lipsum_words`]: fn.lipsum_words.html
-pub fn lipsum(n: usize) -> String {
-    LOREM_IPSUM_CHAIN.with(|chain| chain.generate_from(n, ("Lorem", "ipsum")))
-}
+    #[test]
+    fn from_file() {
+        let path = std::env::temp_dir().join(format!("lipsum-{}.txt", std::process::id()));
+        std::fs::write(&path, "red green blue red green yellow").unwrap();
+        let corpus = MarkovChain::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
 
-/// Generate `n` words of lorem ipsum text with a custom RNG. The output will
-/// always start with "Lorem ipsum".
-///
-/// A custom RNG allows to base the markov chain on a different random number
-/// sequence. This also allows using a regular [`thread_rng`] random number
-/// generator. If that generator is used, the text will differ in each
-/// invocation.
-///
-/// # Examples
-///
-/// ```
-/// use lipsum::lipsum_with_rng;
-/// use rand::thread_rng;
-///
-/// println!("{}", lipsum_with_rng(thread_rng(), 23));
-/// // -> "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do
-/// //     eiusmod tempor incididunt ut labore et dolore magnam aliquam
-/// //     quaerat voluptatem. Ut enim."
-/// ```
-///
-/// [`thread_rng`]: https://docs.rs/rand/latest/rand/fn.thread_rng.html
-pub fn lipsum_with_rng(rng: impl Rng, n: usize) -> String {
-    LOREM_IPSUM_CHAIN.with(|chain| chain.generate_with_rng_from(rng, n, ("Lorem", "ipsum")))
-}
+        let corpus = corpus.unwrap();
+        assert_eq!(corpus.text(), "red green blue red green yellow");
+        let chain = corpus.chain();
+        assert_eq!(chain.words(("red", "green")), Some(&vec!["blue", "yellow"]));
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(
+            chain
+                .generate_with_rng(&mut rng, 5)
+                .split_whitespace()
+                .count(),
+            5
+        );
+    }
 
-/// Generate `n` words of lorem ipsum text.
-///
-/// The text is deterministically sampled from a Markov chain based on
-/// [`LOREM_IPSUM`]. Multiple sentences may be generated, depending on the
-/// punctuation of the words being selected.
-///
-/// # Examples
-///
-/// ```
-/// use lipsum::lipsum_words;
-///
-/// assert_eq!(lipsum_words(6), "Ullus investigandi veri, nisi inveneris, et.");
-/// ```
-///
-/// [`LOREM_IPSUM`]: constant.LOREM_IPSUM.html
-pub fn lipsum_words(n: usize) -> String {
-    LOREM_IPSUM_CHAIN.with(|chain| chain.generate(n))
-}
+    #[test]
+    fn from_file_missing() {
+        let path = std::env::temp_dir().join("lipsum-does-not-exist.txt");
+        let error = MarkovChain::from_file(path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
 
-/// Generate `n` words of lorem ipsum text with a custom RNG.
-///
-/// A custom RNG allows to base the markov chain on a different random number
-/// sequence. This also allows using a regular [`thread_rng`] random number
-/// generator. If that generator is used, the text will differ in each
-/// invocation.
-///
-/// # Examples
-///
-/// ```
-/// use lipsum::lipsum_words_with_rng;
-/// use rand::thread_rng;
-///
-/// println!("{}", lipsum_words_with_rng(thread_rng(), 7));
-/// // -> "Quot homines, tot sententiae; falli igitur possumus."
-/// ```
-///
-/// [`thread_rng`]: https://docs.rs/rand/latest/rand/fn.thread_rng.html
-pub fn lipsum_words_with_rng(rng: impl Rng, n: usize) -> String {
-    LOREM_IPSUM_CHAIN.with(|chain| chain.generate_with_rng(rng, n))
-}
+    #[test]
+    fn quote_structure() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for _ in 0..20 {
+            let quote = lipsum_quote_with_rng(&mut rng);
+            let (sentence, name) = quote.split_once(" — ").unwrap();
+            assert!(sentence.starts_with('“'), "{:?}", quote);
+            assert!(sentence.ends_with('”'), "{:?}", quote);
+            let words = name.split(' ').collect::<Vec<_>>();
+            assert_eq!(words.len(), 2, "{:?}", quote);
+            for word in words {
+                assert!(word.starts_with(char::is_uppercase), "{:?}", quote);
+            }
+        }
+    }
+
+    #[test]
+    fn generate_distinct() {
+        let chain = super::default_chain();
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let samples = chain.generate_distinct(rng, 10, 20);
+        assert_eq!(samples.len(), 10);
+        for (i, sample) in samples.iter().enumerate() {
+            assert_eq!(sample.split_whitespace().count(), 20);
+            for previous in &samples[..i] {
+                let shared = super::similarity(&word_trigrams(sample), &word_trigrams(previous));
+                assert!(
+                    shared <= MAX_DISTINCT_SIMILARITY,
+                    "{:?} vs {:?}",
+                    sample,
+                    previous
+                );
+            }
+        }
+    }
 
-/// Minimum number of words to include in a title.
-const TITLE_MIN_WORDS: usize = 3;
-/// Maximum number of words to include in a title.
-const TITLE_MAX_WORDS: usize = 8;
-/// Words shorter than this size are not capitalized.
-const TITLE_SMALL_WORD: usize = 3;
+    #[test]
+    fn generate_distinct_best_effort() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c");
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(chain.generate_distinct(rng, 3, 10).len(), 3);
+    }
 
-/// Generate a short lorem ipsum text with words in title case.
-///
-/// The words are capitalized and stripped for punctuation characters.
-///
-/// # Examples
-///
-/// ```
-/// use lipsum::lipsum_title;
-///
-/// println!("{}", lipsum_title());
-/// ```
-///
-/// This will generate a string like
-///
-/// > Grate Meminit et Praesentibus
-///
-/// which should be suitable for use in a document title for section
-/// heading.
-pub fn lipsum_title() -> String {
-    lipsum_title_with_rng(default_rng())
-}
+    #[test]
+    fn to_probabilities_json() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c a b d a b c");
+        chain.learn_sentence("x \"y\" z");
 
-/// Generate a short lorem ipsum text with words in title case with a custom RNG.
-///
-/// A custom RNG allows to base the markov chain on a different random number
-/// sequence. This also allows using a regular [`thread_rng`] random number
-/// generator. If that generator is used, the text will differ in each
-/// invocation.
-///
-/// The words are capitalized and stripped for punctuation characters.
-///
-/// # Examples
-///
-/// ```
-/// use lipsum::lipsum_title_with_rng;
-/// use rand::thread_rng;
-///
-/// println!("{}", lipsum_title_with_rng(thread_rng()));
-/// ```
-///
-/// This will generate a string like
-///
-/// > Grate Meminit et Praesentibus
-///
-/// which should be suitable for use in a document title for section
-/// heading.
-///
-/// [`thread_rng`]: https://docs.rs/rand/latest/rand/fn.thread_rng.html
-pub fn lipsum_title_with_rng(mut rng: impl Rng) -> String {
-    LOREM_IPSUM_CHAIN.with(|chain| {
-        let n = rng.gen_range(TITLE_MIN_WORDS..TITLE_MAX_WORDS);
-        // The average word length with our corpus is 7.6 bytes so
-        // this capacity will avoid most allocations.
-        let mut title = String::with_capacity(8 * n);
+        let json: serde_json::Value = serde_json::from_str(&chain.to_probabilities_json()).unwrap();
+        let states = json.as_array().unwrap();
+        assert_eq!(states.len(), chain.len());
 
-        let words = chain
-            .iter_with_rng(rng)
-            .map(|word| word.trim_matches(is_ascii_punctuation))
-            .filter(|word| !word.is_empty())
-            .take(n);
+        let find = |a: &str, b: &str| {
+            states
+                .iter()
+                .find(|state| state["bigram"] == serde_json::json!([a, b]))
+        };
+        let state = find("a", "b").unwrap();
+        let c = state["successors"]["c"].as_f64().unwrap();
+        let d = state["successors"]["d"].as_f64().unwrap();
+        assert!((c - 2.0 / 3.0).abs() < 1e-9);
+        assert!((d - 1.0 / 3.0).abs() < 1e-9);
 
-        for (i, word) in words.enumerate() {
-            if i > 0 {
-                title.push(' ');
+        for state in states {
+            let successors = state["successors"].as_object().unwrap();
+            let sum = successors
+                .values()
+                .map(|p| p.as_f64().unwrap())
+                .sum::<f64>();
+            assert!((sum - 1.0).abs() < 1e-9, "{}", state);
+        }
+        assert_eq!(find("\"y\"", "z").unwrap()["successors"][""], 1.0);
+    }
+
+    #[test]
+    fn generate_with_keywords() {
+        let chain = super::default_chain();
+        let keywords = ["Widget", "acme", "dolor"];
+        for seed in 0..10 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = chain.generate_with_keywords(rng, 30, &keywords);
+            assert_eq!(text.split_whitespace().count(), 30);
+            let words = text
+                .split_whitespace()
+                .map(|word| word.trim_matches(is_ascii_punctuation).to_lowercase())
+                .collect::<Vec<_>>();
+            for keyword in &keywords {
+                assert!(
+                    words.contains(&keyword.to_lowercase()),
+                    "{:?} in {:?}",
+                    keyword,
+                    text
+                );
             }
+        }
+    }
 
-            // Capitalize the first word and all long words.
-            if i == 0 || word.len() > TITLE_SMALL_WORD {
-                title.push_str(&capitalize(word));
-            } else {
-                title.push_str(word);
+    #[test]
+    fn generate_with_keywords_more_than_n() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c d");
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_keywords(rng, 1, &["x", "y", "x"]);
+        assert_eq!(text, "X y.");
+    }
+
+    #[test]
+    fn lipsum_block_fits() {
+        for (cols, lines) in [(1, 1), (5, 3), (10, 10), (40, 5), (80, 24)] {
+            let block = lipsum_block_with_rng(thread_rng(), cols, lines);
+            assert!(block.lines().count() <= lines, "{:?}", block);
+            for line in block.lines() {
+                assert!(!line.is_empty(), "{:?}", block);
+                assert!(line.chars().count() <= cols, "{:?}", block);
             }
         }
-        title
-    })
-}
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use rand::thread_rng;
+    #[test]
+    fn lipsum_block_empty() {
+        assert_eq!(lipsum_block(0, 5), "");
+        assert_eq!(lipsum_block(5, 0), "");
+    }
 
     #[test]
-    fn starts_with_lorem_ipsum() {
-        assert_eq!(&lipsum(10)[..11], "Lorem ipsum");
+    fn terminator_weights_mix() {
+        let mut chain = MarkovChain::new();
+        chain.learn("alpha beta gamma delta epsilon zeta eta theta alpha beta");
+        let options = Options::new()
+            .max_sentence_chars(20)
+            .terminator_weights([2, 1, 1]);
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_options(rng, 200, &options);
+        for terminator in TERMINATORS {
+            assert!(text.contains(*terminator), "{:?} in {:?}", terminator, text);
+        }
+        assert!(text.matches('.').count() > text.matches('!').count());
     }
 
     #[test]
-    fn generate_zero_words() {
-        assert_eq!(lipsum(0).split_whitespace().count(), 0);
+    fn terminator_weights_keep_learned() {
+        let mut chain = MarkovChain::new();
+        chain.learn("one. two. three. one. two.");
+        let options = Options::new().terminator_weights([0, 0, 1]);
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_options(rng, 10, &options);
+        assert!(!text.contains('?'), "{:?}", text);
     }
 
     #[test]
-    fn generate_one_word() {
-        assert_eq!(lipsum(1).split_whitespace().count(), 1);
+    fn terminator_weights_french() {
+        let mut chain = MarkovChain::new();
+        chain.learn("alpha beta gamma delta");
+        let options = Options::new()
+            .punctuation_style(PunctuationStyle::French)
+            .terminator_weights([0, 1, 0]);
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_with_options(rng, 3, &options);
+        assert!(text.ends_with("\u{202f}!"), "{:?}", text);
     }
 
     #[test]
-    fn generate_two_words() {
-        assert_eq!(lipsum(2).split_whitespace().count(), 2);
+    fn lipsum_comment_prefix() {
+        for style in [CommentStyle::Line, CommentStyle::Block] {
+            let comment = lipsum_comment_with_rng(thread_rng(), style, 100);
+            assert!(comment.lines().count() > 1);
+            for line in comment.lines() {
+                assert!(line.starts_with(style.prefix()), "{:?}", line);
+                assert!(line.chars().count() <= COMMENT_WIDTH, "{:?}", line);
+            }
+        }
     }
 
     #[test]
-    fn starts_differently() {
-        // Check that calls to lipsum_words don't always start with
-        // "Lorem ipsum".
-        let idx = "Lorem ipsum".len();
-        assert_ne!(
-            &lipsum_words_with_rng(thread_rng(), 5)[..idx],
-            &lipsum_words_with_rng(thread_rng(), 5)[..idx]
-        );
+    fn lipsum_comment_empty() {
+        assert_eq!(lipsum_comment(CommentStyle::Block, 0), "");
     }
 
     #[test]
-    fn generate_title() {
-        for word in lipsum_title().split_whitespace() {
-            assert!(
-                !word.starts_with(is_ascii_punctuation) && !word.ends_with(is_ascii_punctuation),
-                "Unexpected punctuation: {:?}",
-                word
-            );
-            if word.len() > TITLE_SMALL_WORD {
-                assert!(
-                    word.starts_with(char::is_uppercase),
-                    "Expected small word to be capitalized: {:?}",
-                    word
-                );
+    fn dead_ends() {
+        let mut chain = MarkovChain::new();
+        chain.learn("a b c");
+        assert_eq!(chain.dead_ends(), vec![("b", "c")]);
+
+        chain.learn("x y z");
+        chain.learn("x y c");
+        assert_eq!(chain.dead_ends(), vec![("b", "c"), ("y", "c"), ("y", "z")]);
+
+        chain.learn("b c a b");
+        assert_eq!(chain.dead_ends(), vec![("y", "c"), ("y", "z")]);
+
+        let mut sentences = MarkovChain::new();
+        sentences.learn_sentence("a b c");
+        assert_eq!(sentences.dead_ends(), vec![]);
+    }
+
+    #[test]
+    fn lipsum_messages_mix() {
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let messages = lipsum_messages_with_rng(rng, 50);
+        assert_eq!(messages.len(), 50);
+
+        let lengths = messages
+            .iter()
+            .map(|message| message.split_whitespace().count())
+            .collect::<HashSet<_>>();
+        assert!(lengths.len() > 3, "{:?}", messages);
+
+        let fragments = messages
+            .iter()
+            .filter(|message| !message.ends_with(TERMINATORS))
+            .collect::<Vec<_>>();
+        assert!(!fragments.is_empty() && fragments.len() < messages.len());
+        for fragment in fragments {
+            let words = fragment.split_whitespace().count();
+            assert!((FRAGMENT_MIN_WORDS..=FRAGMENT_MAX_WORDS).contains(&words));
+            assert!(fragment.starts_with(char::is_uppercase), "{:?}", fragment);
+        }
+    }
+
+    #[test]
+    fn lipsum_at_least_ends_sentence() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for min_words in 1..50 {
+            let text = lipsum_at_least_with_rng(&mut rng, min_words);
+            assert!(text.split_whitespace().count() >= min_words, "{:?}", text);
+            assert!(text.ends_with(TERMINATORS), "{:?}", text);
+            assert!(!text.ends_with(",."), "{:?}", text);
+        }
+        assert_eq!(lipsum_at_least(0), "");
+    }
+
+    #[test]
+    fn lipsum_startcase_capitalized() {
+        let mut rng = ChaCha20Rng::seed_from_u64(0);
+        for n in 0..20 {
+            let text = lipsum_startcase_with_rng(&mut rng, n);
+            assert_eq!(text.split(' ').filter(|word| !word.is_empty()).count(), n);
+            for word in text.split_whitespace() {
+                assert!(word.starts_with(char::is_uppercase), "{:?}", text);
+                assert!(!word.ends_with(is_ascii_punctuation), "{:?}", text);
             }
         }
     }
 
     #[test]
-    fn capitalize_after_punctiation() {
-        // The Markov Chain will yield a "habitut." as the second word. However,
-        // the following "voluptatem" is not capitalized, which does not make
-        // much sense, given that it appears after a full stop. The `join_words`
-        // must ensure that every word appearing after sentence-ending
-        // punctuation is capitalized.
+    fn stats() {
+        let mut chain = MarkovChain::new();
         assert_eq!(
-            lipsum_words_with_rng(ChaCha20Rng::seed_from_u64(5), 9),
-            "Nullam habuit. Voluptatem cum summum bonum in voluptate est."
+            chain.stats(),
+            ChainStats {
+                states: 0,
+                tokens: 0,
+                distinct_words: 0,
+                avg_word_len: 0.0,
+                avg_successors: 0.0,
+                dead_ends: 0,
+            }
+        );
+
+        chain.learn("a bb c a bb ddd a bb c");
+        chain.learn_sentence("x yy z");
+        let stats = chain.stats();
+        assert_eq!(stats.states, chain.len());
+        assert_eq!(stats.tokens, 12);
+        assert_eq!(stats.distinct_words, chain.distinct_words());
+        assert_eq!(stats.avg_word_len, 11.0 / 7.0);
+        assert_eq!(stats.dead_ends, chain.dead_ends().len());
+        // Only ("a", "bb") has two distinct successors.
+        assert_eq!(
+            stats.avg_successors,
+            (chain.len() + 1) as f64 / chain.len() as f64
         );
     }
 
     #[test]
-    fn empty_chain() {
+    fn generate_like_template() {
+        let sentence_lengths = |text: &str| {
+            let mut lengths = Vec::new();
+            let mut count = 0;
+            for word in text.split_whitespace() {
+                count += 1;
+                if word.ends_with(TERMINATORS) {
+                    lengths.push(count);
+                    count = 0;
+                }
+            }
+            lengths
+        };
+
+        let chain = super::default_chain();
+        let template =
+            "One. Two words! Here are three? And now a much longer sentence with nine words.";
+        for seed in 0..10 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = chain.generate_like(rng, template);
+            assert_eq!(sentence_lengths(&text), [1, 2, 3, 9], "{:?}", text);
+        }
+
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = chain.generate_like(rng, "no terminator here");
+        assert_eq!(sentence_lengths(&text), [3], "{:?}", text);
+    }
+
+    #[test]
+    fn write_sentence() {
+        let mut chain = MarkovChain::new();
+        chain.learn("one two three, four five. six seven eight! nine ten eleven");
+        let mut words = chain.iter_with_rng_from(ChaCha20Rng::seed_from_u64(0), ("one", "two"));
+        let mut sentences = Vec::new();
+        for _ in 0..2 {
+            let mut sentence = String::new();
+            words.write_sentence(&mut sentence).unwrap();
+            sentences.push(sentence);
+        }
+        assert_eq!(sentences, ["One two three, four five.", "Six seven eight!"]);
+
+        let reference =
+            chain.generate_with_rng_from(ChaCha20Rng::seed_from_u64(0), 5, ("one", "two"));
+        assert_eq!(sentences[0], reference);
+    }
+
+    #[test]
+    fn write_sentence_streams() {
+        // Records the pieces written, which must not be whole sentences.
+        struct Pieces(Vec<String>);
+        impl std::fmt::Write for Pieces {
+            fn write_str(&mut self, s: &str) -> std::fmt::Result {
+                self.0.push(s.to_string());
+                Ok(())
+            }
+        }
+
+        let mut chain = MarkovChain::new();
+        chain.learn(LIBER_PRIMUS);
+        chain.learn("x y x y");
+        for seed in 0..20 {
+            let mut streamed = chain.iter_with_rng(ChaCha20Rng::seed_from_u64(seed));
+            let mut reference = chain.iter_with_rng(ChaCha20Rng::seed_from_u64(seed));
+            let options = Options::new();
+            for _ in 0..3 {
+                let mut pieces = Pieces(Vec::new());
+                streamed.write_sentence(&mut pieces).unwrap();
+                let mut builder = TextBuilder::new(&options);
+                reference.push_sentence(&mut builder);
+                let sentence = builder.finish();
+                assert_eq!(pieces.0.concat(), sentence);
+                if sentence.contains(' ') {
+                    assert!(pieces.0.len() > 1);
+                }
+            }
+        }
+
+        // No terminators: the sentence is ended after 50 words.
+        let mut chain = MarkovChain::new();
+        chain.learn("a, b, a, b,");
+        let mut sentence = String::new();
+        chain.iter().write_sentence(&mut sentence).unwrap();
+        assert_eq!(sentence.split(' ').count(), SENTENCE_MAX_WORDS);
+        assert!(sentence.starts_with(['A', 'B']), "{:?}", sentence);
+        assert!(sentence.ends_with("a.") || sentence.ends_with("b."));
+    }
+
+    #[test]
+    fn write_sentence_empty() {
         let chain = MarkovChain::new();
-        assert_eq!(chain.generate(10), "");
+        let mut text = String::new();
+        chain.iter().write_sentence(&mut text).unwrap();
+        assert_eq!(text, "");
     }
 
     #[test]
-    fn generate_from() {
+    fn no_repeat_state_window_breaks_cycle() {
         let mut chain = MarkovChain::new();
-        chain.learn("red orange yellow green blue indigo violet");
+        chain.learn("a b a b a b x y a b a y z a b a b");
+        assert_eq!(chain.dead_ends(), vec![]);
+
+        let cycles = |options: &Options| {
+            let rng = ChaCha20Rng::seed_from_u64(0);
+            chain
+                .iter_with_options(rng, options)
+                .take(200)
+                .collect::<Vec<_>>()
+                .windows(4)
+                .filter(|window| *window == ["a", "b", "a", "b"])
+                .count()
+        };
+        assert!(cycles(&Options::new()) > 0);
+        assert_eq!(cycles(&Options::new().no_repeat_state_window(2)), 0);
+    }
+
+    #[test]
+    fn generate_from_most_diverse() {
+        let mut chain = MarkovChain::new();
+        chain.learn("x y a x y a x y a x y a p q b p q c p q d");
+        let diverse = chain.most_diverse_bigram().unwrap();
+        assert_eq!(diverse, ("p", "q"));
+        let max_successors = chain
+            .keys
+            .iter()
+            .map(|key| super::count_words(&chain.map[key]).len())
+            .max()
+            .unwrap();
         assert_eq!(
-            chain.generate_from(5, ("orange", "yellow")),
-            "Orange yellow green blue indigo."
+            super::count_words(&chain.map[&diverse]).len(),
+            max_successors
         );
+
+        for seed in 0..5 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = chain.generate_from_most_diverse(rng, 4);
+            assert!(text.starts_with("P q "), "{:?}", text);
+        }
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(MarkovChain::new().generate_from_most_diverse(rng, 4), "");
     }
 
     #[test]
-    fn generate_last_bigram() {
-        // The bigram "yyy zzz" will not be present in the Markov
-        // chain's map, and so we will not generate "xxx yyy zzz" as
-        // one would expect. The chain moves from state "xxx yyy" to
-        // "yyy zzz", but sees that as invalid state and resets itself
-        // back to "xxx yyy".
+    fn transform_leet() {
+        assert_eq!(
+            Transform::Leet.apply("Aeiost AEIOST xyz!"),
+            "431057 431057 xyz!"
+        );
+        let text = lipsum_styled_with_rng(thread_rng(), 20, Transform::Leet);
+        assert!(!text.contains(&['a', 'e', 'i', 'o', 's', 't', 'A', 'E', 'I', 'O', 'S', 'T'][..]));
+        assert_eq!(text.split_whitespace().count(), 20);
+    }
+
+    #[test]
+    fn transform_none() {
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let text = lipsum_styled_with_rng(rng, 20, Transform::default());
+        assert_eq!(text, lipsum_with_rng(ChaCha20Rng::seed_from_u64(0), 20));
+    }
+
+    #[test]
+    fn temperature_nan() {
         let mut chain = MarkovChain::new();
-        chain.learn("xxx yyy zzz");
-        assert_ne!(chain.generate_from(3, ("xxx", "yyy")), "xxx yyy zzz");
+        chain.learn("a b c a b c a b d a b");
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        let nan = chain.generate_with_options(rng, 20, &Options::new().temperature(f64::NAN));
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(nan, chain.generate_with_rng(rng, 20));
     }
 
     #[test]
-    fn generate_from_no_panic() {
-        // No panic when asked to generate a chain from a starting
-        // point that doesn't exist in the chain.
+    fn temperature_extreme() {
         let mut chain = MarkovChain::new();
-        chain.learn("foo bar baz");
-        chain.generate_from(3, ("xxx", "yyy"));
+        chain.learn("a b c a b c a b d a b");
+        for temperature in [f64::MIN_POSITIVE, 1e-300, f64::INFINITY, f64::MAX] {
+            let options = Options::new().temperature(temperature);
+            let rng = ChaCha20Rng::seed_from_u64(0);
+            let text = chain.generate_with_options(rng, 20, &options);
+            assert_eq!(text.split_whitespace().count(), 20);
+        }
     }
 
     #[test]
-    fn chain_map() {
+    fn paragraph_breaks_not_counted_as_words() {
         let mut chain = MarkovChain::new();
-        chain.learn("foo bar baz quuz");
-        let map = &chain.map;
+        chain.learn_paragraphs("One two three.\n\nFour five six.\n\nOne two three.");
+        let n = 7;
+        for seed in 0..20 {
+            let rng = || ChaCha20Rng::seed_from_u64(seed);
 
-        assert_eq!(map.len(), 2);
-        assert_eq!(map[&("foo", "bar")], vec!["baz"]);
-        assert_eq!(map[&("bar", "baz")], vec!["quuz"]);
+            let mut buf = Vec::new();
+            chain.generate_to_writer(rng(), n, &mut buf).unwrap();
+            let text = String::from_utf8(buf).unwrap();
+            assert_eq!(text.split_whitespace().count(), n, "{:?}", text);
+
+            let mut text = String::new();
+            chain.generate_into(rng(), n, &mut text);
+            assert_eq!(text.split_whitespace().count(), n, "{:?}", text);
+
+            let text = chain.generate_bounded(rng(), n, 100);
+            assert_eq!(text.split_whitespace().count(), n, "{:?}", text);
+
+            let text = chain.generate_plain(rng(), n);
+            assert_eq!(text.split(' ').count(), n, "{:?}", text);
+            assert!(!text.contains(PARAGRAPH_BREAK), "{:?}", text);
+
+            let text = chain.generate_with_rng_from(rng(), n, ("Four", "five"));
+            assert_eq!(text.split_whitespace().count(), n, "{:?}", text);
+
+            let text = chain.generate_targeted_length(rng(), 30, 4);
+            assert_eq!(text.split_whitespace().count(), 30, "{:?}", text);
+
+            let mut words = chain.iter_with_rng(rng());
+            for _ in 0..30 {
+                assert_ne!(words.next(), Some(PARAGRAPH_BREAK));
+                if words.after_paragraph_break() {
+                    assert!(words.next().is_some());
+                    assert!(!words.after_paragraph_break());
+                }
+            }
+        }
+        let text = chain.generate_greedy(n, ("Four", "five"));
+        assert_eq!(text.split_whitespace().count(), n, "{:?}", text);
+    }
+
+    #[test]
+    fn generate_with_selector_paragraphs() {
+        let mut chain = MarkovChain::new();
+        chain.learn_paragraphs("One two three.\n\nFour five six.\n\nOne two four.");
+        for seed in 0..10 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = chain.generate_with_selector(rng, 30, FrequencySelector);
+            assert_eq!(text.split_whitespace().count(), 30, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn generate_like_paragraphs() {
+        let mut chain = MarkovChain::new();
+        chain.learn_paragraphs("One two three.\n\nFour five six.\n\nOne two four.");
+        let template = "a b c d e f g h. i j k l m n o p";
+        for seed in 0..10 {
+            let rng = ChaCha20Rng::seed_from_u64(seed);
+            let text = chain.generate_like(rng, template);
+            assert_eq!(text.split_whitespace().count(), 16, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn generate_like_only_terminators() {
+        let mut chain = MarkovChain::new();
+        chain.learn("! ? . ! ? .");
+        let rng = ChaCha20Rng::seed_from_u64(0);
+        assert_eq!(chain.generate_like(rng, "a b c."), "");
     }
 
     #[test]